@@ -0,0 +1,108 @@
+use chrono::NaiveDateTime;
+use crate::config::{BrightnessConfig, Schedule, ScheduleMode};
+use crate::data::SensorData;
+use crate::schedule;
+use std::sync::Arc;
+
+const LEVEL_STEP: u8 = 5;
+
+pub fn auto_level(config: &BrightnessConfig, sensor_data: &[Arc<SensorData>], local_now: NaiveDateTime) -> u8 {
+    let level = match ambient_reading(config, sensor_data) {
+        Some(reading) => ambient_level(config, reading),
+        None if is_night(config, local_now) => config.night_level,
+        None => config.day_level
+    };
+
+    quantize(level.min(100))
+}
+
+fn ambient_reading(config: &BrightnessConfig, sensor_data: &[Arc<SensorData>]) -> Option<f32> {
+    let key = config.ambient_key.as_ref()?;
+
+    sensor_data.iter().rev()
+        .filter(|d| config.ambient_reporter.as_ref().is_none_or(|r| *r == d.reporter))
+        .find_map(|d| d.values.get(key))
+        .and_then(|value| value.trim().parse::<f32>().ok())
+}
+
+fn ambient_level(config: &BrightnessConfig, reading: f32) -> u8 {
+    let range = config.ambient_bright - config.ambient_dark;
+    let fraction = if range > 0.0 { ((reading - config.ambient_dark) / range).clamp(0.0, 1.0) } else { 1.0 };
+    let night = config.night_level as f32;
+    let day = config.day_level as f32;
+
+    (night + (day - night) * fraction).round() as u8
+}
+
+fn is_night(config: &BrightnessConfig, local_now: NaiveDateTime) -> bool {
+    match (&config.night_start, &config.night_end) {
+        (Some(start), Some(end)) => schedule::is_active(&Schedule {
+            days: None,
+            start: start.clone(),
+            end: end.clone(),
+            mode: ScheduleMode::THRESHOLD,
+            presence_threshold_secs: None
+        }, local_now),
+        _ => false
+    }
+}
+
+fn quantize(level: u8) -> u8 {
+    ((level as u32 + LEVEL_STEP as u32 / 2) / LEVEL_STEP as u32 * LEVEL_STEP as u32).min(100) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use std::collections::HashMap;
+    use std::time::Instant;
+
+    fn config(toml_str: &str) -> BrightnessConfig {
+        let config: crate::config::Config = toml::from_str(&format!("[brightness]\n{}", toml_str)).unwrap();
+        config.brightness.unwrap()
+    }
+
+    fn at(hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd(2021, 6, 7).and_hms(hour, 0, 0)
+    }
+
+    fn light(reporter: &str, level: &str) -> Arc<SensorData> {
+        let mut values = HashMap::new();
+        values.insert("hue_light_level".to_string(), level.to_string());
+        Arc::new(SensorData { reporter: reporter.to_string(), values, received: Instant::now() })
+    }
+
+    #[test]
+    fn uses_day_and_night_levels() {
+        let config = config("night_start = \"21:00\"\nnight_end = \"07:00\"\nday_level = 90\nnight_level = 20");
+
+        assert_eq!(auto_level(&config, &[], at(12)), 90);
+        assert_eq!(auto_level(&config, &[], at(23)), 20);
+        assert_eq!(auto_level(&config, &[], at(6)), 20);
+    }
+
+    #[test]
+    fn follows_latest_ambient_reading() {
+        let config = config("ambient_key = \"hue_light_level\"\nambient_reporter = \"hue-sensor-agent\"\nambient_dark = 0.0\nambient_bright = 200.0\nday_level = 100\nnight_level = 20");
+        let data = vec![light("hue-sensor-agent", "0"), light("hue-sensor-agent", "100"), light("other-agent", "0")];
+
+        assert_eq!(auto_level(&config, &data, at(12)), 60);
+        assert_eq!(auto_level(&config, &data[..1], at(12)), 20);
+        assert_eq!(auto_level(&config, &[light("hue-sensor-agent", "5000")], at(12)), 100);
+    }
+
+    #[test]
+    fn falls_back_to_time_without_ambient_reading() {
+        let config = config("ambient_key = \"hue_light_level\"\nnight_start = \"21:00\"\nnight_end = \"07:00\"");
+
+        assert_eq!(auto_level(&config, &[], at(23)), 30);
+    }
+
+    #[test]
+    fn quantizes_levels() {
+        assert_eq!(quantize(62), 60);
+        assert_eq!(quantize(63), 65);
+        assert_eq!(quantize(100), 100);
+    }
+}
@@ -0,0 +1,122 @@
+use chrono::NaiveDateTime;
+use crate::config::BurnInConfig;
+use std::time::{Duration, Instant};
+
+// Offsets visited in turn, scaled by shift_pixels, so the layout orbits its original position.
+const SHIFT_PATTERN: [(i32, i32); 8] = [(0, 0), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1)];
+
+pub struct LayoutTracker {
+    layout: String,
+    since: Instant
+}
+
+impl LayoutTracker {
+    pub fn new(now: Instant) -> LayoutTracker {
+        LayoutTracker { layout: String::new(), since: now }
+    }
+
+    pub fn observe(&mut self, layout: &str, now: Instant) {
+        if self.layout != layout {
+            self.layout = layout.to_string();
+            self.since = now;
+        }
+    }
+
+    pub fn reset(&mut self, now: Instant) {
+        self.since = now;
+    }
+
+    pub fn unchanged_for(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.since)
+    }
+}
+
+pub fn pixel_shift(config: &BurnInConfig, local_now: NaiveDateTime) -> (i32, i32) {
+    let step = match config.shift_interval_mins {
+        Some(mins) if mins > 0 => local_now.timestamp() / (mins as i64 * 60),
+        _ => return (0, 0)
+    };
+    let (x, y) = SHIFT_PATTERN[step.rem_euclid(SHIFT_PATTERN.len() as i64) as usize];
+
+    (x * config.shift_pixels, y * config.shift_pixels)
+}
+
+// Progress of a running sweep from 0.0 to 1.0 and the time until it ends.
+pub fn sweep(config: &BurnInConfig, local_now: NaiveDateTime) -> Option<(f32, Duration)> {
+    let interval_ms = config.sweep_interval_mins? as i64 * 60_000;
+    let sweep_ms = config.sweep_secs as i64 * 1000;
+
+    if interval_ms <= 0 || sweep_ms <= 0 {
+        return None;
+    }
+
+    let into_interval = local_now.timestamp_millis().rem_euclid(interval_ms);
+    if into_interval < sweep_ms {
+        Some((into_interval as f32 / sweep_ms as f32, Duration::from_millis((sweep_ms - into_interval) as u64)))
+    } else {
+        None
+    }
+}
+
+pub fn screensaver_active(config: &BurnInConfig, layout: &LayoutTracker, now: Instant) -> bool {
+    match config.screensaver_after_hours {
+        Some(hours) => layout.unchanged_for(now) >= Duration::from_secs(hours as u64 * 3600),
+        None => false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn config(toml_str: &str) -> BurnInConfig {
+        let config: crate::config::Config = toml::from_str(&format!("[burn_in]\n{}", toml_str)).unwrap();
+        config.burn_in.unwrap()
+    }
+
+    fn at(hour: u32, min: u32, sec: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd(2021, 6, 7).and_hms(hour, min, sec)
+    }
+
+    #[test]
+    fn shifts_layout_every_interval() {
+        let config = config("shift_interval_mins = 5\nshift_pixels = 3");
+        let shifts: Vec<(i32, i32)> = (0..9).map(|step| pixel_shift(&config, at(12, step * 5, 0))).collect();
+
+        assert_eq!(shifts[0], (0, 0));
+        assert_eq!(shifts[1], (3, 0));
+        assert_eq!(shifts[6], (-3, -3));
+        assert_eq!(shifts[8], shifts[0]);
+        assert_eq!(pixel_shift(&config, at(12, 4, 59)), (0, 0));
+        assert_eq!(pixel_shift(&self::config(""), at(12, 5, 0)), (0, 0));
+    }
+
+    #[test]
+    fn sweeps_at_start_of_each_interval() {
+        let config = config("sweep_interval_mins = 30\nsweep_secs = 10");
+
+        assert_eq!(sweep(&config, at(12, 30, 5)), Some((0.5, Duration::from_secs(5))));
+        assert_eq!(sweep(&config, at(12, 30, 10)), None);
+        assert_eq!(sweep(&config, at(12, 45, 0)), None);
+        assert_eq!(sweep(&self::config(""), at(12, 30, 5)), None);
+    }
+
+    #[test]
+    fn screensaver_starts_after_unchanged_layout() {
+        let config = config("screensaver_after_hours = 2");
+        let t0 = Instant::now();
+        let mut layout = LayoutTracker::new(t0);
+
+        layout.observe("linux", t0);
+        layout.observe("linux", t0 + Duration::from_secs(3600));
+        assert!(!screensaver_active(&config, &layout, t0 + Duration::from_secs(7199)));
+        assert!(screensaver_active(&config, &layout, t0 + Duration::from_secs(7200)));
+
+        layout.observe("windows", t0 + Duration::from_secs(7200));
+        assert!(!screensaver_active(&config, &layout, t0 + Duration::from_secs(7300)));
+
+        layout.reset(t0 + Duration::from_secs(20000));
+        assert!(!screensaver_active(&config, &layout, t0 + Duration::from_secs(20000)));
+    }
+}
@@ -0,0 +1,60 @@
+use std::time::Instant;
+use chrono::{Local, NaiveDateTime};
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+    fn local_now(&self) -> NaiveDateTime;
+}
+
+pub struct SystemClock();
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn local_now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+}
+
+#[cfg(test)]
+pub use self::manual::ManualClock;
+
+#[cfg(test)]
+mod manual {
+    use super::Clock;
+    use std::sync::Mutex;
+    use std::time::{Instant, Duration};
+    use chrono::{NaiveDateTime, NaiveDate};
+
+    pub struct ManualClock {
+        start: Instant,
+        local_start: NaiveDateTime,
+        elapsed: Mutex<Duration>
+    }
+
+    impl ManualClock {
+        pub fn new() -> ManualClock {
+            ManualClock {
+                start: Instant::now(),
+                local_start: NaiveDate::from_ymd(2021, 6, 7).and_hms(12, 0, 0),
+                elapsed: Mutex::new(Duration::from_secs(0))
+            }
+        }
+
+        pub fn advance(&self, duration: Duration) {
+            *self.elapsed.lock().unwrap() += duration;
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Instant {
+            self.start + *self.elapsed.lock().unwrap()
+        }
+
+        fn local_now(&self) -> NaiveDateTime {
+            self.local_start + chrono::Duration::from_std(*self.elapsed.lock().unwrap()).unwrap()
+        }
+    }
+}
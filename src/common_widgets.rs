@@ -1,13 +1,17 @@
 use std::collections::HashMap;
-use chrono::Local;
+use chrono::{Local, NaiveDateTime};
 use raylib::color::Color;
-use raylib::drawing::RaylibDraw;
+use raylib::consts::BlendMode;
+use raylib::drawing::{RaylibDraw, RaylibBlendModeExt};
 use raylib::math::Vector2;
-use raylib::prelude::{Font, RaylibDrawHandle};
+use raylib::prelude::Font;
 use crate::data::SensorData;
 use crate::fonts::get_font;
+use crate::state::{CarouselState, Notification, Severity};
+use crate::config::SweepMode;
+use std::time::Instant;
 
-pub fn draw_time_panel(d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &HashMap<String, Font>, hue: &Vec<&SensorData>, crypto: &Vec<&SensorData>, aws: &Vec<&SensorData>) {
+pub fn draw_time_panel<D: RaylibDraw>(d: &mut D, x: i32, y: i32, fonts: &HashMap<String, Font>, hue: &Vec<&SensorData>, crypto: &Vec<&SensorData>, aws: &Vec<&SensorData>) {
 
     let latest_hue = hue.last();
     let latest_crypto = crypto.last();
@@ -62,3 +66,79 @@ fn get_diff_color(diff: f32) -> Color {
         _ => Color::WHITE
     };
 }
+
+
+pub fn draw_notifications<D: RaylibDraw>(d: &mut D, fonts: &HashMap<String, Font>, notifications: &[Notification], now: Instant, width: i32) {
+    let active = notifications.iter()
+        .filter(|n| n.expires > now)
+        .collect::<Vec<&Notification>>();
+
+    for (index, notification) in active.iter().rev().enumerate() {
+        let x = width - 420;
+        let y = 10 + index as i32 * 80;
+        let severity_color = match notification.severity {
+            Severity::INFO => Color::SKYBLUE,
+            Severity::WARNING => Color::ORANGE,
+            Severity::ERROR => Color::RED
+        };
+
+        d.draw_rectangle(x, y, 410, 70, Color::new(20, 20, 20, 230));
+        d.draw_rectangle_lines(x, y, 410, 70, Color::DARKGRAY);
+        d.draw_rectangle(x, y, 8, 70, severity_color);
+        d.draw_text_ex(get_font(fonts, "calibri_25_bold"), &notification.title, Vector2::new((x + 20) as f32, (y + 10) as f32), 25.0, 0.0, Color::WHITE);
+        d.draw_text_ex(get_font(fonts, "calibri_20"), &notification.body, Vector2::new((x + 20) as f32, (y + 40) as f32), 20.0, 0.0, Color::LIGHTGRAY);
+    }
+}
+
+const SWEEP_WIDTH: i32 = 96;
+
+pub fn draw_sweep<D: RaylibDraw>(d: &mut D, mode: &SweepMode, progress: f32, (width, height): (i32, i32)) {
+    let x = (progress * (width + SWEEP_WIDTH) as f32) as i32 - SWEEP_WIDTH;
+
+    match mode {
+        SweepMode::INVERT => {
+            // Subtracting the framebuffer from white inverts the pixels under the bar.
+            let mut inverted = d.begin_blend_mode(BlendMode::BLEND_SUBTRACT_COLORS);
+            inverted.draw_rectangle(x, 0, SWEEP_WIDTH, height, Color::WHITE);
+        }
+        SweepMode::BLANK => d.draw_rectangle(x, 0, SWEEP_WIDTH, height, Color::BLACK)
+    }
+}
+
+pub fn draw_screensaver<D: RaylibDraw>(d: &mut D, fonts: &HashMap<String, Font>, local_now: NaiveDateTime, (width, height): (i32, i32)) {
+    let text = local_now.format("%H:%M").to_string();
+    let minute = local_now.timestamp() / 60;
+
+    d.clear_background(Color::BLACK);
+    d.draw_text_ex(get_font(fonts, "calibri_50_bold"), &text,
+                   Vector2::new((minute * 89 % (width as i64 - 144)) as f32, (minute * 53 % (height as i64 - 60)) as f32), 50.0, 0.0, Color::DARKGRAY);
+}
+
+const PAGE_DOT_SPACING: i32 = 16;
+
+// Dots at the bottom of the panel with the current carousel page filled, and a pause sign in
+// front of them while the carousel is paused.
+pub fn draw_page_indicator<D: RaylibDraw>(d: &mut D, carousel: &CarouselState, (width, height): (i32, i32)) {
+    let pages = carousel.pages as i32;
+    let x = (width - (pages - 1) * PAGE_DOT_SPACING) / 2;
+    let y = height - 12;
+
+    for page in 0..pages {
+        let color = if page == carousel.page as i32 { Color::DARKGRAY } else { Color::LIGHTGRAY };
+        d.draw_circle(x + page * PAGE_DOT_SPACING, y, 4.0, color);
+    }
+
+    if carousel.paused_since.is_some() {
+        d.draw_rectangle(x - PAGE_DOT_SPACING - 4, y - 5, 3, 10, Color::DARKGRAY);
+        d.draw_rectangle(x - PAGE_DOT_SPACING + 1, y - 5, 3, 10, Color::DARKGRAY);
+    }
+}
+
+pub fn draw_dim_overlay<D: RaylibDraw>(d: &mut D, brightness: u8, (width, height): (i32, i32)) {
+    if brightness >= 100 {
+        return;
+    }
+
+    let alpha = (255 * (100 - brightness as u32) / 100) as u8;
+    d.draw_rectangle(0, 0, width, height, Color::new(0, 0, 0, alpha));
+}
\ No newline at end of file
@@ -1,7 +1,11 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::fs;
+use std::process;
+use crate::config_check::{check_syntax, check_resources, has_errors, DiagnosticLevel};
+use crate::log::{Log, LogExt, LogLevel};
+use crate::overrides::{self, Override};
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Config {
     #[serde(default = "default_resources")]
     pub resources: String,
@@ -10,18 +14,419 @@ pub struct Config {
     #[serde(default = "default_presence_threshold_secs")]
     pub presence_threshold_secs: u32,
     #[serde(default = "default_fps")]
-    pub fps: u32
+    pub fps: u32,
+    #[serde(default = "default_presence_sources")]
+    pub presence_sources: Vec<PresenceSource>,
+    #[serde(default = "default_presence_mode")]
+    pub presence_mode: PresenceMode,
+    #[serde(default)]
+    pub schedules: Vec<Schedule>,
+    pub host_idle: Option<HostIdle>,
+    #[serde(default = "default_screen_control")]
+    pub screen_control: ScreenControlConfig,
+    pub brightness: Option<BrightnessConfig>,
+    pub usb_power: Option<UsbPowerConfig>,
+    #[serde(default = "default_transitions")]
+    pub transitions: TransitionConfig,
+    pub burn_in: Option<BurnInConfig>,
+    #[serde(default = "default_window")]
+    pub window: WindowConfig,
+    #[serde(default = "default_panel_rules")]
+    pub panel_rules: Vec<PanelRule>,
+    #[serde(default = "default_panel_selection")]
+    pub panel_selection: PanelSelectionConfig,
+    #[serde(default)]
+    pub carousel_pages: Vec<CarouselPage>,
+    #[serde(default = "default_carousel")]
+    pub carousel: CarouselConfig,
+    #[serde(default = "default_overview")]
+    pub overview: OverviewConfig
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PanelRule {
+    pub panel: String,
+    pub reporter: String,
+    pub key: Option<String>,
+    pub equals: Option<String>,
+    pub above: Option<f32>,
+    pub below: Option<f32>,
+    pub stale_secs: Option<u64>
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PanelSelectionConfig {
+    #[serde(default = "default_stale_secs")]
+    pub stale_secs: u64,
+    #[serde(default = "default_fallback_panel")]
+    pub fallback: String,
+    #[serde(default)]
+    pub switch_delay_secs: u64
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CarouselPage {
+    pub panel: String,
+    pub dwell_secs: Option<u64>
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CarouselConfig {
+    #[serde(default = "default_dwell_secs")]
+    pub dwell_secs: u64,
+    pub resume_after_secs: Option<u64>,
+    #[serde(default = "default_indicator")]
+    pub indicator: bool
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct OverviewConfig {
+    #[serde(default = "default_drop_offline_secs")]
+    pub drop_offline_secs: u64
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct WindowConfig {
+    #[serde(default = "default_window_width")]
+    pub width: i32,
+    #[serde(default = "default_window_height")]
+    pub height: i32,
+    #[serde(default = "default_window_title")]
+    pub title: String,
+    #[serde(default = "default_window_mode")]
+    pub mode: WindowMode,
+    pub monitor: Option<i32>,
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+    #[serde(default)]
+    pub hide_cursor: bool,
+    #[serde(default)]
+    pub rotation: u32
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowMode {
+    WINDOWED,
+    FULLSCREEN,
+    BORDERLESS
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct BurnInConfig {
+    pub shift_interval_mins: Option<u32>,
+    #[serde(default = "default_shift_pixels")]
+    pub shift_pixels: i32,
+    pub sweep_interval_mins: Option<u32>,
+    #[serde(default = "default_sweep_mode")]
+    pub sweep_mode: SweepMode,
+    #[serde(default = "default_sweep_secs")]
+    pub sweep_secs: u32,
+    pub screensaver_after_hours: Option<u32>
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SweepMode {
+    INVERT,
+    BLANK
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TransitionConfig {
+    #[serde(default = "default_fade_in_ms")]
+    pub fade_in_ms: u64,
+    #[serde(default = "default_fade_out_ms")]
+    pub fade_out_ms: u64,
+    pub pending_dim_level: Option<u8>
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct UsbPowerConfig {
+    #[serde(default = "default_uhubctl_path")]
+    pub path: String,
+    pub location: Option<String>,
+    pub ports: Option<String>,
+    pub retries: Option<u32>,
+    pub on_command: Option<Vec<String>>,
+    pub off_command: Option<Vec<String>>
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct BrightnessConfig {
+    pub backlight: Option<String>,
+    #[serde(default = "default_day_level")]
+    pub day_level: u8,
+    #[serde(default = "default_night_level")]
+    pub night_level: u8,
+    pub night_start: Option<String>,
+    pub night_end: Option<String>,
+    pub ambient_reporter: Option<String>,
+    pub ambient_key: Option<String>,
+    #[serde(default)]
+    pub ambient_dark: f32,
+    #[serde(default = "default_ambient_bright")]
+    pub ambient_bright: f32
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct HostIdle {
+    pub primary_reporters: Vec<String>,
+    pub timeout_mins: u32,
+    pub idle_key: Option<String>,
+    pub idle_below: Option<f32>
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PresenceSource {
+    pub reporter: Option<String>,
+    pub key: String,
+    pub equals: Option<String>,
+    pub above: Option<f32>,
+    pub below: Option<f32>,
+    pub max_age_secs: Option<u64>
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PresenceMode {
+    ANY,
+    ALL
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Schedule {
+    pub days: Option<String>,
+    pub start: String,
+    pub end: String,
+    pub mode: ScheduleMode,
+    pub presence_threshold_secs: Option<u32>
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleMode {
+    OFF,
+    ON,
+    THRESHOLD
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ScreenControlConfig {
+    #[serde(default = "default_screen_backend")]
+    pub backend: ScreenBackend,
+    pub path: Option<String>,
+    pub display: Option<String>,
+    pub backlight: Option<String>,
+    pub cec_address: Option<String>,
+    pub on_command: Option<Vec<String>>,
+    pub off_command: Option<Vec<String>>,
+    pub clear_screen: Option<bool>,
+    pub status_command: Option<Vec<String>>,
+    pub status_on: Option<String>,
+    #[serde(default = "default_screen_retries")]
+    pub retries: u32,
+    #[serde(default = "default_screen_retry_delay_ms")]
+    pub retry_delay_ms: u64,
+    #[serde(default)]
+    pub verify: bool
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ScreenBackend {
+    AUTO,
+    TVSERVICE,
+    VCGENCMD,
+    XSET,
+    BACKLIGHT,
+    CEC,
+    COMMAND,
+    SOFTWARE
+}
+
+// Keys accepted in each table, "" being the top level. Keep in sync with the structs above,
+// check-config warns about any other key.
+pub const CONFIG_KEYS: &[(&str, &[&str])] = &[
+    ("", &["resources", "relay_host", "presence_threshold_secs", "fps", "presence_sources", "presence_mode",
+           "schedules", "host_idle", "screen_control", "brightness", "usb_power", "transitions", "burn_in",
+           "window", "panel_rules", "panel_selection", "carousel_pages", "carousel", "overview"]),
+    ("presence_sources", &["reporter", "key", "equals", "above", "below", "max_age_secs"]),
+    ("schedules", &["days", "start", "end", "mode", "presence_threshold_secs"]),
+    ("host_idle", &["primary_reporters", "timeout_mins", "idle_key", "idle_below"]),
+    ("screen_control", &["backend", "path", "display", "backlight", "cec_address", "on_command", "off_command",
+                         "clear_screen", "status_command", "status_on", "retries", "retry_delay_ms", "verify"]),
+    ("brightness", &["backlight", "day_level", "night_level", "night_start", "night_end", "ambient_reporter",
+                     "ambient_key", "ambient_dark", "ambient_bright"]),
+    ("usb_power", &["path", "location", "ports", "retries", "on_command", "off_command"]),
+    ("transitions", &["fade_in_ms", "fade_out_ms", "pending_dim_level"]),
+    ("burn_in", &["shift_interval_mins", "shift_pixels", "sweep_interval_mins", "sweep_mode", "sweep_secs",
+                  "screensaver_after_hours"]),
+    ("panel_rules", &["panel", "reporter", "key", "equals", "above", "below", "stale_secs"]),
+    ("panel_selection", &["stale_secs", "fallback", "switch_delay_secs"]),
+    ("carousel_pages", &["panel", "dwell_secs"]),
+    ("carousel", &["dwell_secs", "resume_after_secs", "indicator"]),
+    ("overview", &["drop_offline_secs"]),
+    ("window", &["width", "height", "title", "mode", "monitor", "x", "y", "hide_cursor", "rotation"])
+];
+
+// Tables given as [[arrays]], addressed as table[index] in diagnostics.
+pub const CONFIG_ARRAYS: [&str; 4] = ["presence_sources", "schedules", "panel_rules", "carousel_pages"];
+
 fn default_resources() -> String { "./resources".to_string() }
 fn default_relay_host() -> String { "127.0.0.1".to_string() }
 fn default_presence_threshold_secs() -> u32 { 600 as u32 }
 fn default_fps() -> u32 { 60 as u32 }
+fn default_presence_sources() -> Vec<PresenceSource> {
+    vec![PresenceSource {
+        reporter: None,
+        key: "hue_presence".to_string(),
+        equals: Some("true".to_string()),
+        above: None,
+        below: None,
+        max_age_secs: None
+    }]
+}
+fn default_panel_rules() -> Vec<PanelRule> {
+    let rule = |panel: &str, reporter: &str| PanelRule {
+        panel: panel.to_string(),
+        reporter: reporter.to_string(),
+        key: None,
+        equals: None,
+        above: None,
+        below: None,
+        stale_secs: None
+    };
+
+    vec![rule("windows", "windows-sensor-agent"), rule("linux", "linux-sensor-agent")]
+}
+fn default_stale_secs() -> u64 { 10 }
+fn default_fallback_panel() -> String { "pending".to_string() }
+fn default_panel_selection() -> PanelSelectionConfig {
+    PanelSelectionConfig {
+        stale_secs: default_stale_secs(),
+        fallback: default_fallback_panel(),
+        switch_delay_secs: 0
+    }
+}
+fn default_dwell_secs() -> u64 { 15 }
+fn default_indicator() -> bool { true }
+fn default_carousel() -> CarouselConfig {
+    CarouselConfig {
+        dwell_secs: default_dwell_secs(),
+        resume_after_secs: None,
+        indicator: default_indicator()
+    }
+}
+fn default_drop_offline_secs() -> u64 { 300 }
+fn default_overview() -> OverviewConfig {
+    OverviewConfig {
+        drop_offline_secs: default_drop_offline_secs()
+    }
+}
+fn default_presence_mode() -> PresenceMode { PresenceMode::ANY }
+fn default_day_level() -> u8 { 100 }
+fn default_night_level() -> u8 { 30 }
+fn default_ambient_bright() -> f32 { 200.0 }
+fn default_fade_in_ms() -> u64 { 500 }
+fn default_fade_out_ms() -> u64 { 1000 }
+fn default_transitions() -> TransitionConfig {
+    TransitionConfig {
+        fade_in_ms: default_fade_in_ms(),
+        fade_out_ms: default_fade_out_ms(),
+        pending_dim_level: None
+    }
+}
+fn default_shift_pixels() -> i32 { 2 }
+fn default_window_width() -> i32 { 1024 }
+fn default_window_height() -> i32 { 600 }
+fn default_window_title() -> String { "SensorPanel".to_string() }
+fn default_window_mode() -> WindowMode { WindowMode::WINDOWED }
+fn default_window() -> WindowConfig {
+    WindowConfig {
+        width: default_window_width(),
+        height: default_window_height(),
+        title: default_window_title(),
+        mode: default_window_mode(),
+        monitor: None,
+        x: None,
+        y: None,
+        hide_cursor: false,
+        rotation: 0
+    }
+}
+fn default_sweep_mode() -> SweepMode { SweepMode::INVERT }
+fn default_sweep_secs() -> u32 { 10 }
+fn default_uhubctl_path() -> String { "uhubctl".to_string() }
+fn default_screen_retries() -> u32 { 2 }
+fn default_screen_retry_delay_ms() -> u64 { 500 }
+fn default_screen_backend() -> ScreenBackend { ScreenBackend::AUTO }
+fn default_screen_control() -> ScreenControlConfig {
+    ScreenControlConfig {
+        backend: default_screen_backend(),
+        path: None,
+        display: None,
+        backlight: None,
+        cec_address: None,
+        on_command: None,
+        off_command: None,
+        clear_screen: None,
+        status_command: None,
+        status_on: None,
+        retries: default_screen_retries(),
+        retry_delay_ms: default_screen_retry_delay_ms(),
+        verify: false
+    }
+}
+
+// Reads and checks the config file, logging every diagnostic. Exits when the file can't be used.
+pub fn read_config(filename: &str, overrides: &[Override]) -> Config {
+    let config_str = fs::read_to_string(filename).unwrap_or_else(|error| {
+        Log::log(LogLevel::ERROR, &*format!("Could not read config file {}: {}", filename, error));
+        process::exit(1);
+    });
+
+    let (config, diagnostics) = check_syntax(&config_str);
+    for diagnostic in diagnostics.iter() {
+        Log::log(diagnostic.log_level(), &diagnostic.describe(filename));
+    }
+
+    if config.is_none() || has_errors(&diagnostics) {
+        Log::log(LogLevel::ERROR, &*format!("Invalid config file {}, run check-config for details", filename));
+        process::exit(1);
+    }
+
+    // Values are checked once overrides are applied
+    let config = overrides::apply(&config_str, overrides).unwrap_or_else(|error| {
+        Log::log(LogLevel::ERROR, &*format!("Invalid config {}: {}", filename, error));
+        process::exit(1);
+    });
+
+    let resource_diagnostics = check_resources(&config, &config_str);
+    for diagnostic in resource_diagnostics.iter() {
+        Log::log(diagnostic.log_level(), &diagnostic.describe(filename));
+    }
+    if has_errors(&resource_diagnostics) {
+        process::exit(1);
+    }
+
+    config
+}
+
+// Parses and checks a config with overrides applied, failing on errors and logging warnings.
+pub fn parse_config(config_str: &str, overrides: &[Override]) -> Result<Config, String> {
+    let (config, diagnostics) = check_syntax(config_str);
+    let mut errors = Vec::new();
 
-pub fn read_config(filename: &str) -> Config {
-    let config_str = fs::read_to_string(filename)
-        .expect("Could not read config file");
+    for diagnostic in diagnostics.iter() {
+        match diagnostic.level {
+            DiagnosticLevel::ERROR => errors.push(diagnostic.describe("config")),
+            DiagnosticLevel::WARNING => Log::log(diagnostic.log_level(), &diagnostic.describe("config"))
+        }
+    }
 
-    return toml::from_str(&config_str.to_string())
-        .expect("Failed to parse config file")
+    match config {
+        Some(_) if errors.is_empty() => overrides::apply(config_str, overrides),
+        _ => Err(errors.join("; "))
+    }
 }
\ No newline at end of file
@@ -0,0 +1,423 @@
+use crate::config::{Config, ScreenBackend, CONFIG_KEYS, CONFIG_ARRAYS};
+use crate::fonts::{FONT_NAMES, font_path};
+use crate::textures::{TEXTURE_FILES, texture_path};
+use crate::log::LogLevel;
+use crate::schedule;
+use crate::viewport::ROTATIONS;
+use crate::layout::{layout_files, parse_layout};
+use crate::panel::PANEL_NAMES;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiagnosticLevel {
+    ERROR,
+    WARNING
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub level: DiagnosticLevel,
+    pub path: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String
+}
+
+impl Diagnostic {
+    // Formats as "file:line:column: level: field.path: message", leaving out what isn't known.
+    pub fn describe(&self, filename: &str) -> String {
+        let location = match (self.line, self.column) {
+            (Some(line), Some(column)) => format!("{}:{}:{}", filename, line, column),
+            (Some(line), None) => format!("{}:{}", filename, line),
+            _ => filename.to_string()
+        };
+        let level = match self.level {
+            DiagnosticLevel::ERROR => "error",
+            DiagnosticLevel::WARNING => "warning"
+        };
+        let path = if self.path.is_empty() { String::new() } else { format!("{}: ", self.path) };
+
+        format!("{}: {}: {}{}", location, level, path, self.message)
+    }
+
+    pub fn log_level(&self) -> LogLevel {
+        match self.level {
+            DiagnosticLevel::ERROR => LogLevel::ERROR,
+            DiagnosticLevel::WARNING => LogLevel::INFO
+        }
+    }
+}
+
+// A key as written in the file, with the table it belongs to and the index of that table for [[arrays]].
+struct KeyLocation {
+    table: String,
+    index: usize,
+    key: String,
+    line: usize,
+    column: usize
+}
+
+// Prints every diagnostic for the file and returns the process exit code.
+pub fn run(filename: &str) -> i32 {
+    let source = match fs::read_to_string(filename) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("{}: error: could not read file: {}", filename, error);
+            return 1;
+        }
+    };
+
+    let (config, mut diagnostics) = check_config(&source);
+    if let Some(config) = &config {
+        diagnostics.extend(check_resources(config, &source));
+    }
+
+    for diagnostic in diagnostics.iter() {
+        eprintln!("{}", diagnostic.describe(filename));
+    }
+
+    if has_errors(&diagnostics) {
+        return 1;
+    }
+
+    println!("{}: ok", filename);
+    0
+}
+
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|d| d.level == DiagnosticLevel::ERROR)
+}
+
+// Checks syntax, types, unknown keys and values. Resources are checked separately by check_resources
+// as they are only loaded at startup.
+pub fn check_config(source: &str) -> (Option<Config>, Vec<Diagnostic>) {
+    let (config, mut diagnostics) = check_syntax(source);
+    let config = match config {
+        Some(config) => config,
+        None => return (None, diagnostics)
+    };
+    let keys = scan_keys(source);
+
+    for (path, message) in check_values(&config) {
+        let (line, column) = locate(&keys, &path);
+        diagnostics.push(Diagnostic { level: DiagnosticLevel::ERROR, path, line, column, message });
+    }
+
+    (Some(config), diagnostics)
+}
+
+// Checks syntax, types and unknown keys only. Values are left to check_values on the config with
+// overrides applied, as an override may fix a value the file gets wrong.
+pub fn check_syntax(source: &str) -> (Option<Config>, Vec<Diagnostic>) {
+    match toml::from_str(source) {
+        Ok(config) => (Some(config), unknown_keys(&scan_keys(source))),
+        Err(error) => (None, vec![parse_error(&error, source)])
+    }
+}
+
+fn parse_error(error: &toml::de::Error, source: &str) -> Diagnostic {
+    let text = error.to_string();
+    let text = text.split(" at line ").next().unwrap_or(&text).to_string();
+    let (message, path) = match text.split_once(" for key `") {
+        Some((message, key)) => (message.to_string(), key.trim_end_matches('`').to_string()),
+        None => (text.clone(), String::new())
+    };
+    // toml reports type errors at the start of the enclosing table, prefer the key itself when it can be found.
+    let (line, column) = match (locate(&scan_keys(source), &path), error.line_col()) {
+        ((Some(line), column), _) => (Some(line), column),
+        (_, Some((line, column))) => (Some(line + 1), Some(column + 1)),
+        _ => (None, None)
+    };
+
+    Diagnostic { level: DiagnosticLevel::ERROR, path, line, column, message }
+}
+
+fn scan_keys(source: &str) -> Vec<KeyLocation> {
+    let mut keys = Vec::new();
+    let mut table = String::new();
+    let mut index = 0;
+    let mut array_counts: Vec<(String, usize)> = Vec::new();
+
+    for (line_index, line) in source.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let column = line.len() - trimmed.len() + 1;
+
+        if let Some(header) = trimmed.strip_prefix("[[") {
+            table = header.split("]]").next().unwrap_or("").trim().to_string();
+            index = match array_counts.iter_mut().find(|(name, _)| *name == table) {
+                Some((_, count)) => { *count += 1; *count - 1 }
+                None => { array_counts.push((table.clone(), 1)); 0 }
+            };
+            keys.push(KeyLocation { table: String::new(), index: 0, key: table.clone(), line: line_index + 1, column: column + 2 });
+        } else if let Some(header) = trimmed.strip_prefix('[') {
+            table = header.split(']').next().unwrap_or("").trim().to_string();
+            index = 0;
+            keys.push(KeyLocation { table: String::new(), index: 0, key: table.clone(), line: line_index + 1, column: column + 1 });
+        } else if let Some((key, _)) = trimmed.split_once('=') {
+            let key = key.trim().trim_matches('"');
+            if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                keys.push(KeyLocation { table: table.clone(), index, key: key.to_string(), line: line_index + 1, column });
+            }
+        }
+    }
+
+    keys
+}
+
+fn unknown_keys(keys: &[KeyLocation]) -> Vec<Diagnostic> {
+    keys.iter().filter_map(|location| {
+        let known = CONFIG_KEYS.iter().find(|(table, _)| *table == location.table).map(|(_, keys)| *keys);
+
+        match known {
+            Some(known) if known.contains(&location.key.as_str()) => None,
+            Some(_) => Some(Diagnostic {
+                level: DiagnosticLevel::WARNING,
+                path: key_path(&location.table, location.index, &location.key),
+                line: Some(location.line),
+                column: Some(location.column),
+                message: "unknown key, it is ignored".to_string()
+            }),
+            // Keys below an unknown table are covered by the warning for the table itself.
+            None => None
+        }
+    }).collect()
+}
+
+fn key_path(table: &str, index: usize, key: &str) -> String {
+    match (table, CONFIG_ARRAYS.contains(&table)) {
+        ("", _) => key.to_string(),
+        (_, true) => format!("{}[{}].{}", table, index, key),
+        _ => format!("{}.{}", table, key)
+    }
+}
+
+fn locate(keys: &[KeyLocation], path: &str) -> (Option<usize>, Option<usize>) {
+    keys.iter()
+        .find(|location| key_path(&location.table, location.index, &location.key) == path)
+        .map_or((None, None), |location| (Some(location.line), Some(location.column)))
+}
+
+pub fn check_values(config: &Config) -> Vec<(String, String)> {
+    let mut problems = Vec::new();
+    let mut check = |ok: bool, path: String, message: String| {
+        if !ok {
+            problems.push((path, message));
+        }
+    };
+    let valid_time = |time: &str| schedule::parse_time(time).is_some();
+
+    check(config.fps > 0, "fps".to_string(), "must be at least 1".to_string());
+
+    for (index, entry) in config.schedules.iter().enumerate() {
+        check(valid_time(&entry.start), format!("schedules[{}].start", index), format!("invalid time '{}', expected HH:MM", entry.start));
+        check(valid_time(&entry.end), format!("schedules[{}].end", index), format!("invalid time '{}', expected HH:MM", entry.end));
+        if let Some(days) = &entry.days {
+            check(schedule::is_valid_days(days), format!("schedules[{}].days", index), format!("invalid days '{}', expected e.g. \"mon-fri\", \"sat,sun\" or \"*\"", days));
+        }
+    }
+
+    if let Some(brightness) = &config.brightness {
+        check(brightness.day_level <= 100, "brightness.day_level".to_string(), "must be between 0 and 100".to_string());
+        check(brightness.night_level <= 100, "brightness.night_level".to_string(), "must be between 0 and 100".to_string());
+        for (key, time) in [("night_start", &brightness.night_start), ("night_end", &brightness.night_end)].iter() {
+            if let Some(time) = time {
+                check(valid_time(time), format!("brightness.{}", key), format!("invalid time '{}', expected HH:MM", time));
+            }
+        }
+    }
+
+    if let Some(level) = config.transitions.pending_dim_level {
+        check(level <= 100, "transitions.pending_dim_level".to_string(), "must be between 0 and 100".to_string());
+    }
+
+    check(config.window.width > 0, "window.width".to_string(), "must be at least 1".to_string());
+    check(config.window.height > 0, "window.height".to_string(), "must be at least 1".to_string());
+    check(ROTATIONS.contains(&config.window.rotation), "window.rotation".to_string(), "must be 0, 90, 180 or 270".to_string());
+    if let Some(monitor) = config.window.monitor {
+        check(monitor >= 0, "window.monitor".to_string(), "must be a monitor index starting at 0".to_string());
+    }
+
+    // Built-in panels and the layouts found in the resources directory
+    let mut panels: Vec<String> = PANEL_NAMES.iter().map(|name| name.to_string()).collect();
+    for (name, _) in layout_files(&config.resources) {
+        if !panels.contains(&name) {
+            panels.push(name);
+        }
+    }
+    let known_panel = |panel: &str| panels.iter().any(|name| name == panel);
+    let panel_names = panels.join(", ");
+    for (index, rule) in config.panel_rules.iter().enumerate() {
+        check(known_panel(&rule.panel), format!("panel_rules[{}].panel", index), format!("unknown panel '{}', expected one of {}", rule.panel, panel_names));
+        check(!rule.reporter.is_empty(), format!("panel_rules[{}].reporter", index), "must not be empty".to_string());
+        check(rule.stale_secs != Some(0), format!("panel_rules[{}].stale_secs", index), "must be at least 1".to_string());
+    }
+    check(config.panel_selection.stale_secs > 0, "panel_selection.stale_secs".to_string(), "must be at least 1".to_string());
+    check(known_panel(&config.panel_selection.fallback), "panel_selection.fallback".to_string(), format!("unknown panel '{}', expected one of {}", config.panel_selection.fallback, panel_names));
+    for (index, page) in config.carousel_pages.iter().enumerate() {
+        check(known_panel(&page.panel), format!("carousel_pages[{}].panel", index), format!("unknown panel '{}', expected one of {}", page.panel, panel_names));
+        check(page.dwell_secs != Some(0), format!("carousel_pages[{}].dwell_secs", index), "must be at least 1".to_string());
+    }
+    check(config.carousel.dwell_secs > 0, "carousel.dwell_secs".to_string(), "must be at least 1".to_string());
+
+    let screen_control = &config.screen_control;
+    if screen_control.backend == ScreenBackend::COMMAND {
+        check(screen_control.on_command.as_ref().is_some_and(|c| !c.is_empty()), "screen_control.on_command".to_string(), "is required by the command backend".to_string());
+        check(screen_control.off_command.as_ref().is_some_and(|c| !c.is_empty()), "screen_control.off_command".to_string(), "is required by the command backend".to_string());
+    }
+
+    if let Some(usb_power) = &config.usb_power {
+        check(usb_power.on_command.is_some() == usb_power.off_command.is_some(), "usb_power".to_string(), "on_command and off_command must be set together".to_string());
+        check(usb_power.on_command.as_ref().is_none_or(|c| !c.is_empty()), "usb_power.on_command".to_string(), "must not be empty".to_string());
+        check(usb_power.off_command.as_ref().is_none_or(|c| !c.is_empty()), "usb_power.off_command".to_string(), "must not be empty".to_string());
+    }
+
+    problems
+}
+
+pub fn check_resources(config: &Config, source: &str) -> Vec<Diagnostic> {
+    let (line, column) = locate(&scan_keys(source), "resources");
+    let error = |message: String| Diagnostic { level: DiagnosticLevel::ERROR, path: "resources".to_string(), line, column, message };
+
+    if !Path::new(&config.resources).is_dir() {
+        return vec![error(format!("directory '{}' does not exist", config.resources))];
+    }
+
+    let mut fonts: Vec<(String, String)> = FONT_NAMES.iter()
+        .map(|font| (format!("font '{}'", font), font_path(&config.resources, font)))
+        .collect();
+    fonts.sort_by(|a, b| a.1.cmp(&b.1));
+    fonts.dedup_by(|a, b| a.1 == b.1);
+    let textures = TEXTURE_FILES.iter()
+        .map(|(name, file)| (format!("texture '{}'", name), texture_path(&config.resources, file)));
+
+    let layouts = layout_files(&config.resources).into_iter()
+        .filter_map(|(name, path)| {
+            let parsed = fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|source| parse_layout(&source));
+            parsed.err().map(|message| error(format!("layout '{}' in {}: {}", name, path, message)))
+        });
+
+    fonts.into_iter().chain(textures)
+        .filter(|(_, path)| !Path::new(path).is_file())
+        .map(|(resource, path)| error(format!("{} not found at {}", resource, path)))
+        .chain(layouts)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    fn check(source: &str) -> Vec<Diagnostic> {
+        check_config(&format!("relay_host = 'relay'\n{}", source)).1
+    }
+
+    #[test]
+    fn valid_config_has_no_diagnostics() {
+        assert_eq!(check("fps = 5\n[[schedules]]\ndays = \"mon-fri\"\nstart = \"23:00\"\nend = \"07:00\"\nmode = \"off\""), vec![]);
+    }
+
+    #[test]
+    fn reports_parse_errors_with_position_and_path() {
+        let (config, diagnostics) = check_config("fps = 5\n\n[screen_control]\nbackend = \"hdmi\"\n");
+
+        assert!(config.is_none());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, "screen_control.backend");
+        assert_eq!(diagnostics[0].line, Some(4));
+        assert!(diagnostics[0].message.starts_with("unknown variant `hdmi`"), "{}", diagnostics[0].message);
+    }
+
+    #[test]
+    fn warns_about_unknown_keys() {
+        let diagnostics = check("fsp = 5\n[screen_control]\nbackend = \"xset\"\ndisplya = \":0\"\n[[schedules]]\nstart = \"23:00\"\nend = \"07:00\"\nmode = \"off\"\n[[schedules]]\nstrat = \"01:00\"\nstart = \"01:00\"\nend = \"02:00\"\nmode = \"on\"");
+        let unknown: Vec<(String, Option<usize>)> = diagnostics.iter()
+            .filter(|d| d.level == DiagnosticLevel::WARNING)
+            .map(|d| (d.path.clone(), d.line))
+            .collect();
+
+        assert_eq!(unknown, vec![("fsp".to_string(), Some(2)), ("screen_control.displya".to_string(), Some(5)), ("schedules[1].strat".to_string(), Some(11))]);
+        assert!(!has_errors(&diagnostics));
+    }
+
+    #[test]
+    fn values_are_checked_after_overrides() {
+        let source = "relay_host = 'relay'\nfps = 0\n[screen_control]\nbackend = \"command\"";
+        let overrides = crate::overrides::from_env(vec![
+            ("SENSORPANEL_FPS".to_string(), "5".to_string()),
+            ("SENSORPANEL_SCREEN_CONTROL_ON_COMMAND".to_string(), "[\"screen\", \"on\"]".to_string()),
+            ("SENSORPANEL_SCREEN_CONTROL_OFF_COMMAND".to_string(), "[\"screen\", \"off\"]".to_string())
+        ].into_iter());
+
+        assert_eq!(check_syntax(source).1, vec![]);
+        assert_eq!(crate::config::parse_config(source, &overrides).unwrap().fps, 5);
+        assert!(crate::config::parse_config(source, &[]).unwrap_err().contains("fps: must be at least 1"));
+    }
+
+    #[test]
+    fn reports_invalid_values_at_their_key() {
+        let diagnostics = check("[[schedules]]\nstart = \"23:00\"\nend = \"25:00\"\ndays = \"weekdays\"\nmode = \"off\"\n[brightness]\nnight_level = 130\n[window]\nwidth = 0\n[usb_power]\non_command = []");
+        let errors: Vec<String> = diagnostics.iter().map(|d| d.describe("config.toml")).collect();
+
+        assert_eq!(errors, vec![
+            "config.toml:4:1: error: schedules[0].end: invalid time '25:00', expected HH:MM".to_string(),
+            "config.toml:5:1: error: schedules[0].days: invalid days 'weekdays', expected e.g. \"mon-fri\", \"sat,sun\" or \"*\"".to_string(),
+            "config.toml:8:1: error: brightness.night_level: must be between 0 and 100".to_string(),
+            "config.toml:10:1: error: window.width: must be at least 1".to_string(),
+            "config.toml:11:2: error: usb_power: on_command and off_command must be set together".to_string(),
+            "config.toml:12:1: error: usb_power.on_command: must not be empty".to_string()
+        ]);
+    }
+
+    #[test]
+    fn panels_must_be_built_in_or_a_layout() {
+        let resources = env::temp_dir().join(format!("sensorpanel-panels-{}", process::id()));
+        fs::create_dir_all(resources.join("layouts")).unwrap();
+        fs::write(resources.join("layouts/gaming.toml"), "").unwrap();
+
+        let diagnostics = check(&format!("resources = '{}'\n\
+                                          [panel_selection]\nfallback = 'idle'\n\n\
+                                          [[panel_rules]]\npanel = 'gaming'\nreporter = 'windows-sensor-agent'\n\n\
+                                          [[carousel_pages]]\npanel = 'overview'\n\n\
+                                          [[carousel_pages]]\npanel = 'graphs'", resources.display()));
+        let errors: Vec<(String, String)> = diagnostics.iter().map(|d| (d.path.clone(), d.message.clone())).collect();
+
+        assert_eq!(errors, vec![
+            ("panel_selection.fallback".to_string(), "unknown panel 'idle', expected one of windows, linux, overview, pending, gaming".to_string()),
+            ("carousel_pages[1].panel".to_string(), "unknown panel 'graphs', expected one of windows, linux, overview, pending, gaming".to_string())
+        ]);
+        fs::remove_dir_all(&resources).unwrap();
+    }
+
+    #[test]
+    fn reports_missing_resources() {
+        let source = "fps = 5\nresources = '/nonexistent/sensorpanel'";
+        let diagnostics = check_resources(&check_config(source).0.unwrap(), source);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].describe("config.toml"), "config.toml:2:1: error: resources: directory '/nonexistent/sensorpanel' does not exist");
+    }
+
+    #[test]
+    fn reports_each_missing_font_and_texture() {
+        let resources = env::temp_dir().join(format!("sensorpanel-resources-{}", process::id()));
+        let present_font = font_path(&resources.display().to_string(), FONT_NAMES[0]);
+        fs::create_dir_all(Path::new(&present_font).parent().unwrap()).unwrap();
+        fs::write(&present_font, "").unwrap();
+        fs::create_dir_all(resources.join("layouts")).unwrap();
+        fs::write(resources.join("layouts/linux.toml"), "[[widgets]]\ntype = 'bar'\nx = 0\ny = 0").unwrap();
+
+        let source = format!("resources = '{}'", resources.display());
+        let diagnostics = check_resources(&check_config(&source).0.unwrap(), &source);
+
+        // Only the bold font file is missing, the regular sizes share the file created above.
+        assert_eq!(diagnostics.len(), 1 + TEXTURE_FILES.len() + 1);
+        assert!(diagnostics.last().unwrap().message.starts_with("layout 'linux' in "));
+        assert!(diagnostics.last().unwrap().message.ends_with("widgets[0].reporter: is required by bar widgets; widgets[0].key: is required by bar widgets; \
+                                                              widgets[0].width: is required by bar widgets; widgets[0].height: is required by bar widgets"));
+        assert!(diagnostics.iter().all(|d| d.path == "resources" && d.line == Some(1)));
+        fs::remove_dir_all(&resources).unwrap();
+    }
+}
@@ -1,16 +1,36 @@
 use raylib::{RaylibThread, RaylibHandle};
 use std::collections::HashMap;
 use raylib::core::text::Font;
-use raylib::core::texture::Texture2D;
+use raylib::core::texture::{RenderTexture2D, Texture2D};
 use std::sync::{Arc, Mutex};
+use arc_swap::ArcSwap;
 use crate::state::State;
-use crate::config::Config;
+use crate::reload::ConfigStore;
+use crate::clock::Clock;
+use crate::redraw::Redraw;
+use crate::burnin::LayoutTracker;
+use crate::viewport::Viewport;
+use crate::layout::Layout;
+use crate::panel::Panels;
+use crate::render::Theme;
+use crate::screenctl::CachedScreenControl;
+use std::time::Instant;
 
 pub(crate) struct Context {
-    pub config: Config,
+    pub config: Arc<ConfigStore>,
     pub thread: RaylibThread,
     pub handle: RaylibHandle,
     pub fonts: HashMap<String, Font>,
     pub textures: HashMap<String, Texture2D>,
-    pub state: Arc<Mutex<State>>
+    pub layouts: HashMap<String, Layout>,
+    pub panels: Panels,
+    pub theme: Theme,
+    pub screen_control: Arc<Mutex<CachedScreenControl>>,
+    pub state: Arc<ArcSwap<State>>,
+    pub clock: Arc<dyn Clock>,
+    pub redraw: Redraw,
+    pub layout: LayoutTracker,
+    pub viewport: Viewport,
+    pub render_target: Option<RenderTexture2D>,
+    pub last_frame: Instant
 }
@@ -1,44 +1,91 @@
 use crate::websocket::SensorReport;
-use crate::state::{State, StateExt};
+use crate::state::{State, StateExt, Action, Severity};
 use crate::config::Config;
 use crate::data::SensorData;
+use crate::log::{Log, LogExt, LogLevel};
+use crate::presence;
+use crate::schedule;
+use crate::idle;
+use crate::brightness;
+use crate::panel;
+use crate::clock::Clock;
+use std::time::{Duration, Instant};
+use std::sync::Arc;
+
+const DEFAULT_MESSAGE_TTL_SECS: u32 = 10;
+const DEFAULT_NOTIFICATION_TTL_SECS: u32 = 30;
 
 pub struct Event();
 
 pub trait EventExt {
-    fn handle(sensor_report: SensorReport, state: &State, config: &Config) -> State;
+    fn handle(sensor_report: SensorReport, state: &State, config: &Config, panels: &[String], clock: &dyn Clock) -> State;
 }
 
 impl EventExt for Event {
-    fn handle(sensor_report: SensorReport, state: &State, config: &Config) -> State {
-        return match sensor_report.topic.as_str() {
+    fn handle(sensor_report: SensorReport, state: &State, config: &Config, panels: &[String], clock: &dyn Clock) -> State {
+        let now = clock.now();
+        let new_state = match sensor_report.topic.as_str() {
             "actions" => {
-                handle_action(sensor_report, state)
+                handle_action(sensor_report, state, panels, now)
             }
             "sensors" => {
-                handle_sensor(sensor_report, state, config)
+                handle_sensor(sensor_report, state, config, clock)
+            }
+            "notifications" => {
+                handle_notification(sensor_report, state, now)
+            }
+            "screen" => {
+                handle_screen(sensor_report, state, now)
             }
             _ => {
                 state.clone()
             }
+        };
+
+        // Checked on every event, ticks included, so sources past max_age_secs age out without new reports
+        let new_state = if new_state.presence.sources.is_empty() {
+            new_state
+        } else {
+            let present = presence::is_present(config, &new_state.presence.sources, now);
+            let threshold_secs = schedule::presence_threshold_secs(config, clock.local_now());
+            new_state.update_presence(present, threshold_secs, now)
+        };
+
+        let mode = schedule::active_schedule(config, clock.local_now()).map(|s| s.mode.clone());
+        let hosts_idle = idle::hosts_idle(config, new_state.last_host_activity, now);
+        let auto_brightness = config.brightness.as_ref()
+            .map(|b| brightness::auto_level(b, &new_state.sensor_data, clock.local_now()));
+
+        let panel = panel::rule_panel(config, &new_state.sensor_data, now);
+        let switch_delay = Duration::from_secs(config.panel_selection.switch_delay_secs);
+
+        let mut new_state = new_state.apply_schedule(mode, now)
+            .apply_hosts_idle(hosts_idle, now)
+            .apply_auto_brightness(auto_brightness)
+            .apply_panel_selection(panel, switch_delay, now)
+            .apply_carousel(&config.carousel_pages, &config.carousel, now);
+
+        if new_state.screen_on != state.screen_on {
+            new_state.screen_switched_at = now;
         }
+
+        return new_state;
     }
 }
 
-fn handle_sensor(event: SensorReport, state: &State, config: &Config) -> State {
+fn handle_sensor(event: SensorReport, state: &State, config: &Config, clock: &dyn Clock) -> State {
     let historical_reports_count = 500;
+    let now = clock.now();
 
     let mut new_state = state.clone();
 
-    if let Some(presence) = event.sensors.get("hue_presence") {
-        handle_presence(presence, state, config);
-    }
+    new_state.presence.sources.extend(presence::read_sources(config, &event.reporter, &event.sensors, now));
 
-    if event.sensors.contains_key("hue_presence") {
-        new_state = handle_presence(event.sensors.get("hue_presence").unwrap(), state, config);
+    if idle::is_activity(config, &event.reporter, &event.sensors) {
+        new_state.last_host_activity = now;
     }
 
-    new_state.sensor_data.push(SensorData { reporter: event.reporter.clone(), values: event.sensors.clone(), received: event.received.clone() });
+    new_state.sensor_data.push(Arc::new(SensorData { reporter: event.reporter, values: event.sensors, received: now }));
     if new_state.sensor_data.len() > historical_reports_count {
         new_state.sensor_data.remove(0);
     }
@@ -46,14 +93,250 @@ fn handle_sensor(event: SensorReport, state: &State, config: &Config) -> State {
     return new_state;
 }
 
-fn handle_presence(presence: &String, state: &State, config: &Config) -> State {
-    state.update_presence(presence == "true", config.presence_threshold_secs)
+fn handle_action(sensor_report: SensorReport, state: &State, panels: &[String], now: Instant) -> State {
+    let mut new_state = state.clone();
+
+    for (key, value) in sensor_report.sensors.iter() {
+        match parse_action(key, value) {
+            Some(action) => new_state = new_state.apply_action(&action, panels, now),
+            None => Log::log(LogLevel::ERROR, &*format!("Unknown or invalid action {} '{}'", key, value))
+        }
+    }
+
+    return new_state;
+}
+
+fn handle_notification(sensor_report: SensorReport, state: &State, now: Instant) -> State {
+    let sensors = &sensor_report.sensors;
+    let title = match sensors.get("title") {
+        Some(title) => title,
+        None => {
+            Log::log(LogLevel::ERROR, &*format!("Notification from {} without title", sensor_report.reporter));
+            return state.clone();
+        }
+    };
+    let body = sensors.get("body").map_or("", |b| b.as_str());
+    let severity = parse_severity(sensors.get("severity").map_or("", |s| s.as_str()));
+    let ttl_secs = sensors.get("ttl")
+        .and_then(|ttl| ttl.trim().parse().ok())
+        .unwrap_or(DEFAULT_NOTIFICATION_TTL_SECS);
+
+    state.push_notification(title, body, severity, ttl_secs, now)
+}
+
+fn handle_screen(sensor_report: SensorReport, state: &State, now: Instant) -> State {
+    let sensors = &sensor_report.sensors;
+    let mut new_state = state.clone();
+
+    new_state.screen_power = match sensors.get("power").map(|p| p.as_str()) {
+        Some("on") => Some(true),
+        Some("off") => Some(false),
+        _ => None
+    };
+    new_state.screen_error = sensors.get("error").cloned();
+
+    if let Some(power) = new_state.screen_power {
+        if power != new_state.screen_on {
+            Log::log(LogLevel::ERROR, &*format!("Screen is {} but should be {}", if power { "on" } else { "off" }, if new_state.screen_on { "on" } else { "off" }));
+        }
+    }
+
+    match &new_state.screen_error {
+        Some(error) if state.screen_error.as_ref() != Some(error) => {
+            new_state.push_notification("Screen control failed", error, Severity::ERROR, DEFAULT_NOTIFICATION_TTL_SECS, now)
+        }
+        _ => new_state
+    }
+}
+
+fn parse_severity(severity: &str) -> Severity {
+    match severity.trim().to_lowercase().as_str() {
+        "warning" | "warn" => Severity::WARNING,
+        "error" | "critical" => Severity::ERROR,
+        _ => Severity::INFO
+    }
+}
+
+fn parse_action(key: &str, value: &str) -> Option<Action> {
+    match key {
+        "toggle_screen" => Some(Action::ToggleScreen),
+        "screen_on" => Some(Action::ScreenOn),
+        "screen_off" => Some(Action::ScreenOff),
+        "screen_auto" => Some(Action::ScreenAuto),
+        "show_panel" => Some(Action::ShowPanel(value.trim().to_string())),
+        "next_panel" => Some(Action::NextPanel),
+        "set_brightness" if value.trim() == "auto" => Some(Action::AutoBrightness),
+        "set_brightness" => value.trim().parse().ok().map(Action::SetBrightness),
+        "reload_config" => Some(Action::ReloadConfig),
+        "show_message" => Some(parse_message(value)),
+        "keep_on_for" => value.trim().parse().ok().map(Action::KeepOnFor),
+        "pause_carousel" => Some(Action::PauseCarousel),
+        "resume_carousel" => Some(Action::ResumeCarousel),
+        "toggle_carousel" => Some(Action::ToggleCarousel),
+        _ => None
+    }
+}
+
+fn parse_message(value: &str) -> Action {
+    let trimmed = value.trim();
+
+    if let Some((text, ttl)) = trimmed.rsplit_once(' ') {
+        if let Ok(ttl_secs) = ttl.parse::<u32>() {
+            return Action::ShowMessage(text.trim().to_string(), ttl_secs);
+        }
+    }
+
+    Action::ShowMessage(trimmed.to_string(), DEFAULT_MESSAGE_TTL_SECS)
 }
 
-fn handle_action(sensor_report: SensorReport, state: &State) -> State {
-    if sensor_report.sensors.contains_key("toggle_screen") {
-        state.toggle_screen_state()
-    } else {
-        state.clone()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::state::Present;
+    use std::time::Duration;
+
+    #[test]
+    fn parses_argumentless_actions() {
+        assert_eq!(parse_action("toggle_screen", ""), Some(Action::ToggleScreen));
+        assert_eq!(parse_action("screen_on", ""), Some(Action::ScreenOn));
+        assert_eq!(parse_action("screen_off", ""), Some(Action::ScreenOff));
+        assert_eq!(parse_action("screen_auto", ""), Some(Action::ScreenAuto));
+        assert_eq!(parse_action("next_panel", ""), Some(Action::NextPanel));
+        assert_eq!(parse_action("reload_config", ""), Some(Action::ReloadConfig));
+        assert_eq!(parse_action("pause_carousel", ""), Some(Action::PauseCarousel));
+        assert_eq!(parse_action("resume_carousel", ""), Some(Action::ResumeCarousel));
+        assert_eq!(parse_action("toggle_carousel", ""), Some(Action::ToggleCarousel));
+    }
+
+    #[test]
+    fn parses_actions_with_arguments() {
+        assert_eq!(parse_action("show_panel", "linux"), Some(Action::ShowPanel("linux".to_string())));
+        assert_eq!(parse_action("set_brightness", "40"), Some(Action::SetBrightness(40)));
+        assert_eq!(parse_action("set_brightness", "auto"), Some(Action::AutoBrightness));
+        assert_eq!(parse_action("keep_on_for", "300"), Some(Action::KeepOnFor(300)));
+    }
+
+    #[test]
+    fn rejects_invalid_actions() {
+        assert_eq!(parse_action("set_brightness", "bright"), None);
+        assert_eq!(parse_action("keep_on_for", "-1"), None);
+        assert_eq!(parse_action("self_destruct", ""), None);
+    }
+
+    fn report(reporter: &str, topic: &str, sensors: Vec<(&str, &str)>) -> SensorReport {
+        SensorReport {
+            reporter: reporter.to_string(),
+            topic: topic.to_string(),
+            sensors: sensors.into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        }
+    }
+
+    fn notification_report(sensors: Vec<(&str, &str)>) -> SensorReport {
+        report("home-automation", "notifications", sensors)
     }
-}
\ No newline at end of file
+
+    fn hue_report(present: &str) -> SensorReport {
+        report("hue-sensor-agent", "sensors", vec![("hue_presence", present)])
+    }
+
+    #[test]
+    fn presence_times_out_without_sleeping() {
+        let config: Config = toml::from_str("presence_threshold_secs = 600").unwrap();
+        let clock = ManualClock::new();
+        let mut state = State::init(clock.now());
+
+        state = Event::handle(hue_report("false"), &state, &config, &[], &clock);
+        assert_eq!(state.presence.present, Present::PENDING);
+
+        clock.advance(Duration::from_secs(601));
+        let previous = state.clone();
+        state = Event::handle(hue_report("false"), &state, &config, &[], &clock);
+        assert_eq!(state.presence.present, Present::NO);
+        assert!(state.state_change_actions(&previous) == vec![Action::ScreenOff]);
+    }
+
+    #[test]
+    fn silent_source_ages_out_on_ticks() {
+        let config: Config = toml::from_str("presence_threshold_secs = 60\n\n\
+                                             [[presence_sources]]\nreporter = 'windows-sensor-agent'\nkey = 'cpu_utilization'\nabove = 5.0\nmax_age_secs = 30").unwrap();
+        let clock = ManualClock::new();
+        let mut state = State::init(clock.now());
+
+        state = Event::handle(report("windows-sensor-agent", "sensors", vec![("cpu_utilization", "40")]), &state, &config, &[], &clock);
+        assert_eq!(state.presence.present, Present::YES);
+
+        clock.advance(Duration::from_secs(31));
+        state = Event::handle(report("sensorpanel", "tick", vec![]), &state, &config, &[], &clock);
+        assert_eq!(state.presence.present, Present::PENDING);
+
+        clock.advance(Duration::from_secs(61));
+        state = Event::handle(report("sensorpanel", "tick", vec![]), &state, &config, &[], &clock);
+        assert_eq!(state.presence.present, Present::NO);
+        assert!(!state.screen_on);
+    }
+
+    #[test]
+    fn idle_hosts_detected_on_tick() {
+        let config: Config = toml::from_str("[host_idle]\nprimary_reporters = [\"linux-sensor-agent\"]\ntimeout_mins = 1").unwrap();
+        let clock = ManualClock::new();
+        let mut state = State::init(clock.now());
+
+        state = Event::handle(report("linux-sensor-agent", "sensors", vec![("cpu_utilization", "10")]), &state, &config, &[], &clock);
+        clock.advance(Duration::from_secs(61));
+        state = Event::handle(report("sensorpanel", "tick", vec![]), &state, &config, &[], &clock);
+        assert!(state.hosts_idle);
+        assert!(!state.screen_on);
+
+        state = Event::handle(report("linux-sensor-agent", "sensors", vec![("cpu_utilization", "10")]), &state, &config, &[], &clock);
+        assert!(!state.hosts_idle);
+        assert!(state.screen_on);
+    }
+
+    #[test]
+    fn queues_notification_report() {
+        let report = notification_report(vec![("title", "Build failed"), ("body", "sensorpanel#42"), ("severity", "error"), ("ttl", "120")]);
+        let state = handle_notification(report, &State::init(Instant::now()), Instant::now());
+
+        assert_eq!(state.notifications.len(), 1);
+        assert_eq!(state.notifications[0].title, "Build failed");
+        assert_eq!(state.notifications[0].body, "sensorpanel#42");
+        assert_eq!(state.notifications[0].severity, Severity::ERROR);
+    }
+
+    #[test]
+    fn ignores_notification_without_title() {
+        let state = handle_notification(notification_report(vec![("body", "No title")]), &State::init(Instant::now()), Instant::now());
+
+        assert!(state.notifications.is_empty());
+    }
+
+    #[test]
+    fn parses_severity_with_info_fallback() {
+        assert_eq!(parse_severity("Warning"), Severity::WARNING);
+        assert_eq!(parse_severity("critical"), Severity::ERROR);
+        assert_eq!(parse_severity("whatever"), Severity::INFO);
+    }
+
+    #[test]
+    fn parses_message_with_and_without_ttl() {
+        assert_eq!(parse_action("show_message", "Washing machine done 60"), Some(Action::ShowMessage("Washing machine done".to_string(), 60)));
+        assert_eq!(parse_action("show_message", "Build failed"), Some(Action::ShowMessage("Build failed".to_string(), DEFAULT_MESSAGE_TTL_SECS)));
+    }
+
+    #[test]
+    fn records_screen_power_and_notifies_on_new_error() {
+        let now = Instant::now();
+        let state = handle_screen(report("sensorpanel", "screen", vec![("power", "on")]), &State::init(now), now);
+        assert_eq!(state.screen_power, Some(true));
+        assert!(state.notifications.is_empty());
+
+        let failed = handle_screen(report("sensorpanel", "screen", vec![("power", "unknown"), ("error", "tvservice exited with 1")]), &state, now);
+        assert_eq!(failed.screen_power, None);
+        assert_eq!(failed.screen_error.as_deref(), Some("tvservice exited with 1"));
+        assert_eq!(failed.notifications.len(), 1);
+
+        let repeated = handle_screen(report("sensorpanel", "screen", vec![("error", "tvservice exited with 1")]), &failed, now);
+        assert_eq!(repeated.notifications.len(), 1);
+    }
+}
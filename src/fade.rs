@@ -0,0 +1,92 @@
+use crate::config::TransitionConfig;
+use crate::state::{State, Present};
+use std::time::{Duration, Instant};
+
+// Visibility of the panel in percent, 0 is fully faded to black.
+pub fn visibility(state: &State, transitions: &TransitionConfig, now: Instant) -> u8 {
+    let elapsed = now.saturating_duration_since(state.screen_switched_at);
+
+    if !state.screen_on {
+        return 100 - progress(elapsed, transitions.fade_out_ms);
+    }
+
+    let level = progress(elapsed, transitions.fade_in_ms);
+
+    match transitions.pending_dim_level {
+        Some(dim_level) if state.presence.present == Present::PENDING => level.min(dim_level),
+        _ => level
+    }
+}
+
+// Remaining time of a running fade, if any.
+pub fn remaining(state: &State, transitions: &TransitionConfig, now: Instant) -> Option<Duration> {
+    let fade_ms = if state.screen_on { transitions.fade_in_ms } else { transitions.fade_out_ms };
+    let end = state.screen_switched_at + Duration::from_millis(fade_ms);
+
+    if end > now { Some(end - now) } else { None }
+}
+
+fn progress(elapsed: Duration, fade_ms: u64) -> u8 {
+    if fade_ms == 0 {
+        return 100;
+    }
+
+    (elapsed.as_millis().min(fade_ms as u128) * 100 / fade_ms as u128) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::StateExt;
+
+    fn transitions(pending_dim_level: Option<u8>) -> TransitionConfig {
+        TransitionConfig { fade_in_ms: 500, fade_out_ms: 1000, pending_dim_level }
+    }
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn fades_in_after_turning_on() {
+        let t0 = Instant::now();
+        let state = State::init(t0);
+
+        assert_eq!(visibility(&state, &transitions(None), t0), 0);
+        assert_eq!(visibility(&state, &transitions(None), t0 + ms(250)), 50);
+        assert_eq!(visibility(&state, &transitions(None), t0 + ms(600)), 100);
+        assert_eq!(remaining(&state, &transitions(None), t0 + ms(100)), Some(ms(400)));
+        assert_eq!(remaining(&state, &transitions(None), t0 + ms(500)), None);
+    }
+
+    #[test]
+    fn fades_out_after_turning_off() {
+        let t0 = Instant::now();
+        let mut state = State::init(t0);
+        state.screen_on = false;
+
+        assert_eq!(visibility(&state, &transitions(None), t0 + ms(250)), 75);
+        assert_eq!(visibility(&state, &transitions(None), t0 + ms(1000)), 0);
+        assert_eq!(remaining(&state, &transitions(None), t0 + ms(250)), Some(ms(750)));
+    }
+
+    #[test]
+    fn dims_while_presence_is_pending() {
+        let t0 = Instant::now();
+        let state = State::init(t0).update_presence(false, 600, t0);
+
+        assert_eq!(state.presence.present, Present::PENDING);
+        assert_eq!(visibility(&state, &transitions(Some(40)), t0 + ms(1000)), 40);
+        assert_eq!(visibility(&state, &transitions(None), t0 + ms(1000)), 100);
+    }
+
+    #[test]
+    fn zero_duration_disables_fades() {
+        let t0 = Instant::now();
+        let state = State::init(t0);
+        let instant = TransitionConfig { fade_in_ms: 0, fade_out_ms: 0, pending_dim_level: None };
+
+        assert_eq!(visibility(&state, &instant, t0), 100);
+        assert_eq!(remaining(&state, &instant, t0), None);
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::time::{Instant, Duration};
+use crate::config::{Config, HostIdle};
+
+pub fn is_activity(config: &Config, reporter: &str, values: &HashMap<String, String>) -> bool {
+    match &config.host_idle {
+        Some(host_idle) => host_idle.primary_reporters.iter().any(|r| r == reporter) && !reports_idle(host_idle, values),
+        None => false
+    }
+}
+
+pub fn hosts_idle(config: &Config, last_activity: Instant, now: Instant) -> bool {
+    match &config.host_idle {
+        Some(host_idle) => now.saturating_duration_since(last_activity) > Duration::from_secs(host_idle.timeout_mins as u64 * 60),
+        None => false
+    }
+}
+
+fn reports_idle(host_idle: &HostIdle, values: &HashMap<String, String>) -> bool {
+    match (&host_idle.idle_key, host_idle.idle_below) {
+        (Some(key), Some(below)) => values.get(key)
+            .and_then(|value| value.trim().parse::<f32>().ok())
+            .is_some_and(|value| value < below),
+        _ => false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        toml::from_str(r#"
+            [host_idle]
+            primary_reporters = ["windows-sensor-agent"]
+            timeout_mins = 15
+            idle_key = "cpu_utilization"
+            idle_below = 3.0
+        "#).unwrap()
+    }
+
+    fn values(cpu_utilization: &str) -> HashMap<String, String> {
+        let mut values = HashMap::new();
+        values.insert("cpu_utilization".to_string(), cpu_utilization.to_string());
+        values
+    }
+
+    #[test]
+    fn only_busy_primary_reports_count_as_activity() {
+        assert!(is_activity(&config(), "windows-sensor-agent", &values("25.0")));
+        assert!(!is_activity(&config(), "windows-sensor-agent", &values("1.5")));
+        assert!(!is_activity(&config(), "hue-sensor-agent", &values("25.0")));
+    }
+
+    #[test]
+    fn hosts_go_idle_after_timeout() {
+        let last_activity = Instant::now();
+
+        assert!(!hosts_idle(&config(), last_activity, last_activity + Duration::from_secs(15 * 60)));
+        assert!(hosts_idle(&config(), last_activity, last_activity + Duration::from_secs(15 * 60 + 1)));
+    }
+
+    #[test]
+    fn disabled_without_config() {
+        let config: Config = toml::from_str("").unwrap();
+
+        assert!(!is_activity(&config, "windows-sensor-agent", &values("25.0")));
+        assert!(!hosts_idle(&config, Instant::now(), Instant::now() + Duration::from_secs(86400)));
+    }
+}
@@ -0,0 +1,350 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use raylib::color::Color;
+use crate::sensors::Sensors;
+use crate::fonts::FONT_NAMES;
+use crate::textures::TEXTURE_FILES;
+use crate::viewport::Orientation;
+use crate::log::{Log, LogExt, LogLevel};
+
+// Layouts shipped with the binary, replaced by a file of the same name in <resources>/layouts.
+pub const DEFAULT_LAYOUTS: [(&str, &str); 2] = [
+    ("windows", include_str!("../layouts/windows.toml")),
+    ("linux", include_str!("../layouts/linux.toml"))
+];
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Layout {
+    pub background: Option<String>,
+    pub background_color: Option<String>,
+    #[serde(default)]
+    pub portrait: bool,
+    #[serde(default)]
+    pub widgets: Vec<Widget>
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Widget {
+    #[serde(rename = "type")]
+    pub kind: WidgetKind,
+    pub x: i32,
+    pub y: i32,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub portrait_x: Option<i32>,
+    pub portrait_y: Option<i32>,
+    pub reporter: Option<String>,
+    #[serde(default)]
+    pub sources: Vec<String>,
+    pub key: Option<String>,
+    #[serde(default)]
+    pub keys: HashMap<String, String>,
+    pub label: Option<String>,
+    pub title: Option<String>,
+    pub unit: Option<String>,
+    pub decimals: Option<usize>,
+    pub max: Option<f32>,
+    pub font: Option<String>,
+    pub color: Option<String>,
+    #[serde(default)]
+    pub graph: bool,
+    pub if_data: Option<String>,
+    pub if_no_data: Option<String>
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+#[allow(non_camel_case_types)]
+pub enum WidgetKind {
+    LINUX_CPU,
+    LINUX_GPU,
+    LINUX_NET,
+    LINUX_CORE,
+    LINUX_MEM,
+    LINUX_TEMP,
+    LINUX_RPM,
+    WINDOWS_CPU,
+    WINDOWS_GPU,
+    WINDOWS_NET,
+    WINDOWS_MEM,
+    WINDOWS_CORE,
+    WINDOWS_HDD,
+    TIME,
+    TEXT,
+    VALUE,
+    BAR
+}
+
+// The sensor keys a built-in widget reads, renamed through its keys table such as
+// keys = { cpu_temp = "cpu_package_temp" }. Keys that aren't renamed are read as is.
+#[derive(Clone, Copy)]
+pub struct Keys<'a>(&'a HashMap<String, String>);
+
+impl<'a> Keys<'a> {
+    pub fn get<'k>(&self, key: &'k str) -> &'k str where 'a: 'k {
+        self.0.get(key).map_or(key, |renamed| renamed.as_str())
+    }
+}
+
+impl WidgetKind {
+    // Keys read by the linux_* and windows_* widgets that can be renamed. Per core, interface and
+    // drive keys are numbered and keep their names.
+    pub fn keys(&self) -> &'static [&'static str] {
+        match self {
+            WidgetKind::LINUX_CPU => &["cpu_utilization", "cpu_temp", "cpu_power"],
+            WidgetKind::LINUX_GPU => &["gpu_utilization", "gpu_edge_temp", "gpu_die_temp", "gpu_junction_temp", "gpu_package_temp",
+                                       "gpu_power", "gpu_voltage", "gpu_frequency", "gpu_fps"],
+            WidgetKind::LINUX_MEM => &["mem_available", "mem_total"],
+            WidgetKind::LINUX_TEMP => &["pump_temp", "front_intake_temp", "exhaust_temp", "ambient_temp"],
+            WidgetKind::LINUX_RPM => &["fan1_rpm", "fan2_rpm", "fan3_rpm", "fan4_rpm", "fan5_rpm", "pump_rpm"],
+            WidgetKind::WINDOWS_CPU => &["cpu_utilization", "cpu_die_temp", "cpu_package_temp", "cpu_power"],
+            WidgetKind::WINDOWS_GPU => &["gpu_utilization", "gpu_die_temp", "gpu_package_temp", "gpu_power", "gpu_voltage", "gpu_frequency", "gpu_fps"],
+            WidgetKind::WINDOWS_MEM => &["mem_used", "mem_available"],
+            _ => &[]
+        }
+    }
+}
+
+impl Widget {
+    pub fn keys(&self) -> Keys<'_> {
+        Keys(&self.keys)
+    }
+
+    pub fn position(&self, layout: Orientation) -> (i32, i32) {
+        match layout {
+            Orientation::LANDSCAPE => (self.x, self.y),
+            Orientation::PORTRAIT => (self.portrait_x.unwrap_or(self.x), self.portrait_y.unwrap_or(self.y))
+        }
+    }
+
+    pub fn is_visible(&self, sensors: &Sensors) -> bool {
+        let has_data = |reporter: &String| sensors.has_data(reporter);
+
+        self.reporter.as_ref().map_or(true, has_data)
+            && self.if_data.as_ref().map_or(true, has_data)
+            && !self.if_no_data.as_ref().is_some_and(has_data)
+    }
+}
+
+pub fn parse_layout(source: &str) -> Result<Layout, String> {
+    let layout: Layout = toml::from_str(source).map_err(|error| error.to_string())?;
+    let problems = check_layout(&layout);
+
+    if problems.is_empty() { Ok(layout) } else { Err(problems.join("; ")) }
+}
+
+pub fn check_layout(layout: &Layout) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut check = |ok: bool, path: String, message: String| {
+        if !ok {
+            problems.push(format!("{}: {}", path, message));
+        }
+    };
+
+    if let Some(background) = &layout.background {
+        check(TEXTURE_FILES.iter().any(|(name, _)| name == background), "background".to_string(), format!("unknown texture '{}'", background));
+    }
+    if let Some(color) = &layout.background_color {
+        check(parse_color(color).is_some(), "background_color".to_string(), format!("invalid colour '{}'", color));
+    }
+
+    for (index, widget) in layout.widgets.iter().enumerate() {
+        let path = |key: &str| format!("widgets[{}].{}", index, key);
+
+        let needs_reporter = !matches!(widget.kind, WidgetKind::TIME | WidgetKind::TEXT);
+        check(!needs_reporter || widget.reporter.is_some(), path("reporter"), format!("is required by {:?} widgets", widget.kind).to_lowercase());
+
+        let needs_key = matches!(widget.kind, WidgetKind::VALUE | WidgetKind::BAR);
+        check(!needs_key || widget.key.is_some(), path("key"), format!("is required by {:?} widgets", widget.kind).to_lowercase());
+
+        if widget.kind == WidgetKind::BAR {
+            check(widget.width.is_some_and(|w| w > 0), path("width"), "is required by bar widgets".to_string());
+            check(widget.height.is_some_and(|h| h > 0), path("height"), "is required by bar widgets".to_string());
+        }
+        if widget.kind == WidgetKind::TEXT {
+            check(widget.label.is_some(), path("label"), "is required by text widgets".to_string());
+        }
+        if widget.title.is_some() {
+            let has_title = matches!(widget.kind, WidgetKind::LINUX_CPU | WidgetKind::LINUX_GPU | WidgetKind::WINDOWS_CPU | WidgetKind::WINDOWS_GPU);
+            check(has_title, path("title"), format!("is not used by {:?} widgets", widget.kind).to_lowercase());
+        }
+        let mut renamed: Vec<&String> = widget.keys.keys().collect();
+        renamed.sort();
+        for key in renamed {
+            let known = widget.kind.keys();
+            let expected = if known.is_empty() { "none".to_string() } else { known.join(", ") };
+            check(known.contains(&key.as_str()), format!("widgets[{}].keys.{}", index, key), format!("unknown key, {} widgets read {}", format!("{:?}", widget.kind).to_lowercase(), expected));
+        }
+        if let Some(font) = &widget.font {
+            check(FONT_NAMES.contains(&font.as_str()), path("font"), format!("unknown font '{}', expected one of {}", font, FONT_NAMES.join(", ")));
+        }
+        if let Some(color) = &widget.color {
+            check(parse_color(color).is_some(), path("color"), format!("invalid colour '{}'", color));
+        }
+    }
+
+    problems
+}
+
+// Colours are given as "#rrggbb", "#rrggbbaa" or a name such as "white".
+pub fn parse_color(color: &str) -> Option<Color> {
+    let named = match color.to_lowercase().as_str() {
+        "white" => Some(Color::WHITE),
+        "black" => Some(Color::BLACK),
+        "gray" | "grey" => Some(Color::GRAY),
+        "lightgray" | "lightgrey" => Some(Color::LIGHTGRAY),
+        "darkgray" | "darkgrey" => Some(Color::DARKGRAY),
+        "red" => Some(Color::RED),
+        "green" => Some(Color::GREEN),
+        "blue" => Some(Color::BLUE),
+        "orange" => Some(Color::ORANGE),
+        "yellow" => Some(Color::YELLOW),
+        "purple" => Some(Color::PURPLE),
+        "skyblue" => Some(Color::SKYBLUE),
+        _ => None
+    };
+
+    named.or_else(|| {
+        let hex = color.strip_prefix('#').filter(|hex| (hex.len() == 6 || hex.len() == 8) && hex.is_ascii())?;
+        let channel = |index: usize| u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).ok();
+        let alpha = if hex.len() == 8 { channel(3)? } else { 255 };
+
+        Some(Color::new(channel(0)?, channel(1)?, channel(2)?, alpha))
+    })
+}
+
+pub fn layout_dir(resources: &str) -> String {
+    format!("{}/layouts", resources)
+}
+
+// Layout files in the resources as (name, path), the name being the file stem.
+pub fn layout_files(resources: &str) -> Vec<(String, String)> {
+    let mut files: Vec<(String, String)> = fs::read_dir(layout_dir(resources)).into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "toml"))
+        .filter_map(|path| Some((path.file_stem()?.to_string_lossy().to_string(), path.display().to_string())))
+        .collect();
+    files.sort();
+
+    files
+}
+
+pub fn load_layouts(resources: &str) -> HashMap<String, Layout> {
+    let mut layouts = HashMap::new();
+
+    for (name, source) in DEFAULT_LAYOUTS.iter() {
+        layouts.insert(name.to_string(), parse_layout(source).expect("Invalid default layout"));
+    }
+
+    for (name, path) in layout_files(resources) {
+        match fs::read_to_string(&path).map_err(|error| error.to_string()).and_then(|source| parse_layout(&source)) {
+            Ok(layout) => {
+                Log::log(LogLevel::INFO, &*format!("Loaded layout {} from {}", name, path));
+                layouts.insert(name, layout);
+            }
+            Err(error) => Log::log(LogLevel::ERROR, &*format!("Ignoring layout {}: {}", path, error))
+        }
+    }
+
+    return layouts;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+    use std::sync::Arc;
+    use std::time::Instant;
+    use crate::data::SensorData;
+
+    fn report(reporter: &str) -> Arc<SensorData> {
+        Arc::new(SensorData { reporter: reporter.to_string(), values: HashMap::new(), received: Instant::now() })
+    }
+
+    #[test]
+    fn default_layouts_are_valid() {
+        for (name, source) in DEFAULT_LAYOUTS.iter() {
+            let layout = parse_layout(source).unwrap_or_else(|error| panic!("{}: {}", name, error));
+            assert!(layout.portrait);
+            assert!(!layout.widgets.is_empty());
+        }
+    }
+
+    #[test]
+    fn reports_invalid_widgets() {
+        let error = parse_layout("[[widgets]]\ntype = 'value'\nx = 0\ny = 0\nfont = 'comic_12'\n\n[[widgets]]\ntype = 'bar'\nx = 0\ny = 0\nreporter = 'r'\nkey = 'k'\ncolor = '#12345'").unwrap_err();
+
+        assert_eq!(error, "widgets[0].reporter: is required by value widgets; widgets[0].key: is required by value widgets; \
+                           widgets[0].font: unknown font 'comic_12', expected one of calibri_13, calibri_15, calibri_20, calibri_25, calibri_30, calibri_25_bold, calibri_40_bold, calibri_50_bold; \
+                           widgets[1].width: is required by bar widgets; widgets[1].height: is required by bar widgets; widgets[1].color: invalid colour '#12345'");
+        assert!(parse_layout("[[widgets]]\ntype = 'text'\nx = 0\ny = 0\nlabel = 'a'\nsize = 3").unwrap_err().contains("unknown field `size`"));
+        assert!(parse_layout("[[widgets]]\ntype = 'clock'\nx = 0\ny = 0").unwrap_err().contains("unknown variant `clock`"));
+    }
+
+    #[test]
+    fn built_in_widgets_read_renamed_keys() {
+        let layout = parse_layout("[[widgets]]\ntype = 'linux_cpu'\nx = 0\ny = 0\nreporter = 'r'\ntitle = 'Xeon'\nkeys = { cpu_temp = 'cpu_package_temp' }").unwrap();
+        let keys = layout.widgets[0].keys();
+
+        assert_eq!(keys.get("cpu_temp"), "cpu_package_temp");
+        assert_eq!(keys.get("cpu_utilization"), "cpu_utilization");
+        assert_eq!(parse_layout("[[widgets]]\ntype = 'linux_core'\nx = 0\ny = 0\nreporter = 'r'\ntitle = 'Cores'\nkeys = { cpu_load = 'load' }").unwrap_err(),
+                   "widgets[0].title: is not used by linux_core widgets; widgets[0].keys.cpu_load: unknown key, linux_core widgets read none");
+        assert_eq!(parse_layout("[[widgets]]\ntype = 'windows_mem'\nx = 0\ny = 0\nreporter = 'r'\nkeys = { mem_free = 'free' }").unwrap_err(),
+                   "widgets[0].keys.mem_free: unknown key, windows_mem widgets read mem_used, mem_available");
+    }
+
+    #[test]
+    fn widgets_follow_reporter_conditions() {
+        let layout = parse_layout(DEFAULT_LAYOUTS[1].1).unwrap();
+        let visible = |data: Vec<Arc<SensorData>>| layout.widgets.iter()
+            .filter(|w| w.is_visible(&Sensors::index(&data)))
+            .map(|w| (w.kind.clone(), w.y))
+            .collect::<Vec<(WidgetKind, i32)>>();
+
+        assert_eq!(visible(vec![]), vec![(WidgetKind::TIME, 560)]);
+        assert!(visible(vec![report("linux-sensor-agent")]).contains(&(WidgetKind::LINUX_GPU, 207)));
+        assert_eq!(visible(vec![report("linux-sensor-agent"), report("linux-guest-sensor-agent")]).iter()
+                       .filter(|(kind, _)| *kind == WidgetKind::LINUX_GPU).collect::<Vec<_>>(),
+                   vec![&(WidgetKind::LINUX_GPU, 197), &(WidgetKind::LINUX_GPU, 307)]);
+    }
+
+    #[test]
+    fn portrait_positions_fall_back_to_landscape() {
+        let layout = parse_layout("[[widgets]]\ntype = 'text'\nlabel = 'a'\nx = 10\ny = 20\nportrait_y = 700").unwrap();
+
+        assert_eq!(layout.widgets[0].position(Orientation::LANDSCAPE), (10, 20));
+        assert_eq!(layout.widgets[0].position(Orientation::PORTRAIT), (10, 700));
+    }
+
+    #[test]
+    fn parses_colours() {
+        assert_eq!(parse_color("White"), Some(Color::WHITE));
+        assert_eq!(parse_color("#ff8000"), Some(Color::new(255, 128, 0, 255)));
+        assert_eq!(parse_color("#ff800080"), Some(Color::new(255, 128, 0, 128)));
+        assert_eq!(parse_color("#ff80"), None);
+        assert_eq!(parse_color("#gg8000"), None);
+        assert_eq!(parse_color("mauve"), None);
+    }
+
+    #[test]
+    fn files_in_resources_replace_defaults() {
+        let resources = env::temp_dir().join(format!("sensorpanel-layouts-{}", process::id()));
+        fs::create_dir_all(resources.join("layouts")).unwrap();
+        fs::write(resources.join("layouts/linux.toml"), "[[widgets]]\ntype = 'text'\nlabel = 'Linux'\nx = 0\ny = 0").unwrap();
+        fs::write(resources.join("layouts/broken.toml"), "widgets = 1").unwrap();
+
+        let layouts = load_layouts(&resources.display().to_string());
+        fs::remove_dir_all(&resources).unwrap();
+
+        assert_eq!(layouts["linux"].widgets.len(), 1);
+        assert!(layouts["windows"].widgets.len() > 1);
+        assert!(!layouts.contains_key("broken"));
+    }
+}
@@ -0,0 +1,107 @@
+use raylib::core::drawing::RaylibDraw;
+use raylib::color::Color;
+use crate::common_widgets::draw_time_panel;
+use crate::data::SensorData;
+use crate::panel::Panel;
+use crate::layout::{Layout, Widget, WidgetKind, parse_color};
+use crate::render::RenderCtx;
+use crate::viewport::Orientation;
+use crate::linux_widgets;
+use crate::windows_widgets;
+
+const DEFAULT_FONT: &str = "calibri_20";
+
+// Draws the widgets of a layout file.
+pub(crate) struct LayoutPanel {
+    layout: Layout
+}
+
+impl LayoutPanel {
+    pub fn new(layout: Layout) -> LayoutPanel {
+        LayoutPanel { layout }
+    }
+}
+
+impl Panel for LayoutPanel {
+    fn draw<D: RaylibDraw>(&mut self, ctx: &mut RenderCtx<D>) {
+        let panel = &self.layout;
+
+        if let Some(color) = panel.background_color.as_deref().and_then(parse_color) {
+            ctx.d.clear_background(color);
+        }
+        if let Some(background) = &panel.background {
+            ctx.d.draw_texture(ctx.texture(background), 0, 0, Color::WHITE);
+        }
+
+        let sensors = ctx.sensors;
+        for widget in panel.widgets.iter().filter(|w| w.is_visible(sensors)) {
+            draw_widget(ctx, widget, widget.position(ctx.layout));
+        }
+    }
+
+    fn layout(&self, orientation: Orientation) -> Orientation {
+        if self.layout.portrait { orientation } else { Orientation::LANDSCAPE }
+    }
+}
+
+fn draw_widget<D: RaylibDraw>(ctx: &mut RenderCtx<D>, widget: &Widget, (x, y): (i32, i32)) {
+    let sensors = ctx.sensors;
+    let (fonts, textures) = (ctx.fonts, ctx.textures);
+    let reporter = sensors.history(widget.reporter.as_deref().unwrap_or(""));
+    let source = |index: usize| sensors.history(widget.sources.get(index).map_or("", |source| source.as_str()));
+    let label = widget.label.as_deref();
+    let title = widget.title.as_deref();
+    let keys = widget.keys();
+    let color = widget.color.as_deref().and_then(parse_color);
+    let text_color = color.unwrap_or(ctx.theme.text);
+    let d = &mut *ctx.d;
+
+    match widget.kind {
+        WidgetKind::LINUX_CPU => linux_widgets::draw_cpu_panel(d, x, y, fonts, textures, reporter, keys, title.unwrap_or("CPU"), label.unwrap_or("")),
+        WidgetKind::LINUX_GPU => linux_widgets::draw_gpu_panel(d, x, y, fonts, textures, reporter, keys, title.unwrap_or("GPU"), label, widget.graph),
+        WidgetKind::LINUX_NET => linux_widgets::draw_net_panel(d, x, y, fonts, reporter, source(0)),
+        WidgetKind::LINUX_CORE => linux_widgets::draw_core_panel(d, x, y, fonts, reporter),
+        WidgetKind::LINUX_MEM => linux_widgets::draw_mem_panel(d, x, y, fonts, reporter, keys),
+        WidgetKind::LINUX_TEMP => linux_widgets::draw_temp_panel(d, x, y, fonts, reporter, keys),
+        WidgetKind::LINUX_RPM => linux_widgets::draw_rpm_panel(d, x, y, fonts, reporter, keys),
+        WidgetKind::WINDOWS_CPU => windows_widgets::draw_cpu_panel(d, x, y, fonts, reporter, keys, title.unwrap_or("CPU")),
+        WidgetKind::WINDOWS_GPU => windows_widgets::draw_gpu_panel(d, x, y, fonts, reporter, keys, title.unwrap_or("GPU")),
+        WidgetKind::WINDOWS_NET => windows_widgets::draw_net_panel(d, x, y, fonts, reporter),
+        WidgetKind::WINDOWS_MEM => windows_widgets::draw_mem_panel(d, x, y, fonts, reporter, keys),
+        WidgetKind::WINDOWS_CORE => windows_widgets::draw_core_panel(d, x, y, fonts, reporter),
+        WidgetKind::WINDOWS_HDD => windows_widgets::draw_hdd_panel(d, x, y, fonts, reporter),
+        WidgetKind::TIME => draw_time_panel(d, x, y, fonts, source(0), source(1), source(2)),
+        WidgetKind::TEXT => ctx.text(widget.font.as_deref().unwrap_or(DEFAULT_FONT), label.unwrap_or(""), (x, y), text_color),
+        WidgetKind::VALUE => {
+            let text = match (label, latest_value(reporter, widget)) {
+                (Some(label), value) => format!("{} {}", label, format_value(widget, value)),
+                (None, value) => format_value(widget, value)
+            };
+            ctx.text(widget.font.as_deref().unwrap_or(DEFAULT_FONT), &text, (x, y), text_color);
+        }
+        WidgetKind::BAR => {
+            let (width, height) = (widget.width.unwrap_or(0), widget.height.unwrap_or(0));
+            let value = latest_value(reporter, widget).and_then(|v| v.parse::<f32>().ok()).unwrap_or(0.0);
+            let filled = (value / widget.max.unwrap_or(100.0)).max(0.0).min(1.0);
+
+            d.draw_rectangle(x, y, width, height, Color::new(20, 20, 20, 255));
+            d.draw_rectangle(x, y, (width as f32 * filled) as i32, height, color.unwrap_or(Color::GREEN));
+            d.draw_rectangle_lines(x, y, width, height, Color::DARKGRAY);
+        }
+    }
+}
+
+fn latest_value<'a>(data: &[&'a SensorData], widget: &Widget) -> Option<&'a String> {
+    let key = widget.key.as_ref()?;
+    data.last().and_then(|latest| latest.values.get(key))
+}
+
+fn format_value(widget: &Widget, value: Option<&String>) -> String {
+    let unit = widget.unit.as_deref().unwrap_or("");
+
+    match (value, value.and_then(|v| v.parse::<f32>().ok())) {
+        (_, Some(number)) => format!("{:.*}{}", widget.decimals.unwrap_or(0), number, unit),
+        (Some(text), None) => format!("{}{}", text, unit),
+        (None, None) => "-".to_string()
+    }
+}
@@ -2,6 +2,7 @@ use raylib::prelude::*;
 use std::collections::HashMap;
 use crate::fonts::get_font;
 use crate::data::SensorData;
+use crate::layout::Keys;
 
 #[cfg(feature = "rpi")]
 fn circle_angle(angle: f32) -> i32 {
@@ -13,7 +14,7 @@ fn circle_angle(angle: f32) -> f32 {
     return angle as f32
 }
 
-pub fn draw_cpu_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &HashMap<String, Font>, images: &HashMap<String, Texture2D>, data: &Vec<&SensorData>) {
+pub fn draw_cpu_panel<D: RaylibDraw>(mut d: &mut D, x: i32, y: i32, fonts: &HashMap<String, Font>, images: &HashMap<String, Texture2D>, data: &Vec<&SensorData>, keys: Keys, title: &str, model: &str) {
 
     let xf = x as f32;
     let yf = y as f32;
@@ -30,17 +31,17 @@ pub fn draw_cpu_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &Hash
         .max()
         .unwrap_or(0);
 
-    let cpu_utilization: f32 = latest_data.values.get("cpu_utilization").unwrap_or(&"0".to_string()).parse().unwrap();
-    let cpu_die_temp: f32 = latest_data.values.get("cpu_temp").unwrap_or(&"0".to_string()).parse().unwrap();
-    //let cpu_package_temp: f32 = latest_data.values.get("cpu_package_temp").unwrap_or(&"0".to_string()).parse().unwrap();
-    let cpu_power: f32 = latest_data.values.get("cpu_power").unwrap_or(&"0".to_string()).parse().unwrap();
+    let cpu_utilization: f32 = latest_data.values.get(keys.get("cpu_utilization")).unwrap_or(&"0".to_string()).parse().unwrap();
+    let cpu_die_temp: f32 = latest_data.values.get(keys.get("cpu_temp")).unwrap_or(&"0".to_string()).parse().unwrap();
+    //let cpu_package_temp: f32 = latest_data.values.get(keys.get("cpu_package_temp")).unwrap_or(&"0".to_string()).parse().unwrap();
+    let cpu_power: f32 = latest_data.values.get(keys.get("cpu_power")).unwrap_or(&"0".to_string()).parse().unwrap();
 
-    d.draw_text_ex(get_font(fonts, "calibri_25_bold"), "Ryzen", Vector2::new(xf + 70.0, yf + 10.0), 25.0, 0.0, Color::WHITE);
-    d.draw_text_ex(get_font(fonts, "calibri_20"), "5950X", Vector2::new(xf + 70.0, yf + 30.0), 20.0, 0.0, Color::WHITE);
+    d.draw_text_ex(get_font(fonts, "calibri_25_bold"), title, Vector2::new(xf + 70.0, yf + 10.0), 25.0, 0.0, Color::WHITE);
+    d.draw_text_ex(get_font(fonts, "calibri_20"), model, Vector2::new(xf + 70.0, yf + 30.0), 20.0, 0.0, Color::WHITE);
 //    d.draw_text_ex(get_font(fonts, "calibri_40_bold"), "CPU", Vector2::new(xf + 10.0, yf + 10.0), 40.0, 0.0, Color::WHITE);
     d.draw_text_ex(get_font(fonts, "calibri_20"), &*format!("{:.2} W", cpu_power), Vector2::new(xf + 150.0, yf + 21.0), 20.0, 0.0, Color::WHITE);
 
-    draw_temperature_gauge(&mut d, x + 235, y + 5, cpu_die_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
+    draw_temperature_gauge(d, x + 235, y + 5, cpu_die_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
 //    draw_temperature_gauge(&mut d, x + 275, y + 5, cpu_package_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
     d.draw_text_ex(get_font(fonts, "calibri_30"), &*format!("{} MHz", max_core_frequency), Vector2::new(xf + 305.0, yf + 18.0), 30.0, 0.0, Color::WHITE);
 
@@ -52,7 +53,7 @@ pub fn draw_cpu_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &Hash
     draw_graph_grid(&mut d, x + 10, y + 100);
 
     let usage_graph_values = &data.iter()
-        .map(|d| d.values.get("cpu_utilization"))
+        .map(|d| d.values.get(keys.get("cpu_utilization")))
         .filter(|util| util.is_some())
         .map(|v| v.unwrap().parse::<f32>().unwrap())
         .collect();
@@ -60,7 +61,7 @@ pub fn draw_cpu_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &Hash
     draw_graph(&mut d, x + 10, y + 100, usage_graph_values, Color::GREEN);
 }
 
-pub fn draw_gpu_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &HashMap<String, Font>, images: &HashMap<String, Texture2D>, data: &Vec<&SensorData>, sub_title: Option<&str>, should_draw_graph: bool) {
+pub fn draw_gpu_panel<D: RaylibDraw>(mut d: &mut D, x: i32, y: i32, fonts: &HashMap<String, Font>, images: &HashMap<String, Texture2D>, data: &Vec<&SensorData>, keys: Keys, title: &str, sub_title: Option<&str>, should_draw_graph: bool) {
 
     let xf = x as f32;
     let yf = y as f32;
@@ -69,33 +70,33 @@ pub fn draw_gpu_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &Hash
 
     d.draw_texture(images.get("amd_logo").unwrap(), x + 10, y + 5, Color::WHITE);
 
-    let gpu_utilization: f32 = latest_data.values.get("gpu_utilization").unwrap_or(&"0".to_string()).parse().unwrap();
-    let gpu_die_temp: f32 = latest_data.values.get("gpu_edge_temp")
-        .or(latest_data.values.get("gpu_die_temp"))
+    let gpu_utilization: f32 = latest_data.values.get(keys.get("gpu_utilization")).unwrap_or(&"0".to_string()).parse().unwrap();
+    let gpu_die_temp: f32 = latest_data.values.get(keys.get("gpu_edge_temp"))
+        .or(latest_data.values.get(keys.get("gpu_die_temp")))
         .unwrap_or(&"0".to_string())
         .parse().unwrap();
 
-    let gpu_package_temp: f32 = latest_data.values.get("gpu_junction_temp")
-        .or(latest_data.values.get("gpu_package_temp"))
+    let gpu_package_temp: f32 = latest_data.values.get(keys.get("gpu_junction_temp"))
+        .or(latest_data.values.get(keys.get("gpu_package_temp")))
         .unwrap_or(&"0".to_string())
         .parse().unwrap();
 
-    let gpu_power: f32 = latest_data.values.get("gpu_power").unwrap_or(&"0".to_string()).parse().unwrap_or(0.0);
-    let gpu_voltage: f32 = latest_data.values.get("gpu_voltage").unwrap_or(&"0".to_string()).parse().unwrap_or(0.0);
-    let gpu_frequency: f32 = latest_data.values.get("gpu_frequency").unwrap_or(&"0".to_string()).parse().unwrap_or(0.0);
-    let gpu_fps: f32 = latest_data.values.get("gpu_fps").unwrap_or(&"0".to_string()).parse().unwrap_or(0.0);
+    let gpu_power: f32 = latest_data.values.get(keys.get("gpu_power")).unwrap_or(&"0".to_string()).parse().unwrap_or(0.0);
+    let gpu_voltage: f32 = latest_data.values.get(keys.get("gpu_voltage")).unwrap_or(&"0".to_string()).parse().unwrap_or(0.0);
+    let gpu_frequency: f32 = latest_data.values.get(keys.get("gpu_frequency")).unwrap_or(&"0".to_string()).parse().unwrap_or(0.0);
+    let gpu_fps: f32 = latest_data.values.get(keys.get("gpu_fps")).unwrap_or(&"0".to_string()).parse().unwrap_or(0.0);
 
     if sub_title.is_some() {
-        d.draw_text_ex(get_font(fonts, "calibri_25_bold"), "Radeon", Vector2::new(xf + 70.0, yf + 10.0), 25.0, 0.0, Color::WHITE);
+        d.draw_text_ex(get_font(fonts, "calibri_25_bold"), title, Vector2::new(xf + 70.0, yf + 10.0), 25.0, 0.0, Color::WHITE);
         d.draw_text_ex(get_font(fonts, "calibri_20"), sub_title.unwrap(), Vector2::new(xf + 70.0, yf + 30.0), 20.0, 0.0, Color::WHITE);
     } else {
-        d.draw_text_ex(get_font(fonts, "calibri_50_bold"), "GPU", Vector2::new(xf + 75.0, yf + 10.0), 50.0, 0.0, Color::WHITE);
+        d.draw_text_ex(get_font(fonts, "calibri_50_bold"), title, Vector2::new(xf + 75.0, yf + 10.0), 50.0, 0.0, Color::WHITE);
     }
     d.draw_text_ex(get_font(fonts, "calibri_20"), &*format!("{:.0} W", gpu_power), Vector2::new(xf + 160.0,  yf + 12.0), 20.0, 0.0, Color::WHITE);
     d.draw_text_ex(get_font(fonts, "calibri_20"), &*format!("{:.2} V", gpu_voltage), Vector2::new(xf + 160.0, yf + 30.0), 20.0, 0.0, Color::WHITE);
 
-    draw_temperature_gauge(&mut d, x + 230, y + 5, gpu_die_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
-    draw_temperature_gauge(&mut d, x + 295, y + 5, gpu_package_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
+    draw_temperature_gauge(d, x + 230, y + 5, gpu_die_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
+    draw_temperature_gauge(d, x + 295, y + 5, gpu_package_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
 
     d.draw_text_ex(get_font(fonts, "calibri_25"), &*format!("{} MHz", gpu_frequency), Vector2::new(xf + 360.0, yf + 7.0), 25.0, 0.0, Color::WHITE);
     d.draw_text_ex(get_font(fonts, "calibri_25"), &*format!("{} FPS", gpu_fps), Vector2::new(xf + 360.0, yf + 32.0), 25.0, 0.0, Color::WHITE);
@@ -108,7 +109,7 @@ pub fn draw_gpu_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &Hash
         draw_graph_grid(&mut d, x + 10, y + 100);
 
         let usage_graph_values = &data.iter()
-            .map(|d| d.values.get("gpu_utilization"))
+            .map(|d| d.values.get(keys.get("gpu_utilization")))
             .filter(|util| util.is_some())
             .map(|v| v.unwrap().parse::<f32>().unwrap())
             .collect();
@@ -117,14 +118,14 @@ pub fn draw_gpu_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &Hash
     }
 }
 
-pub fn draw_mem_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &Vec<&SensorData>) {
+pub fn draw_mem_panel<D: RaylibDraw>(mut d: &mut D, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &Vec<&SensorData>, keys: Keys) {
 
     let xf = x as f32;
     let yf = y as f32;
     let latest_data = data.last().unwrap();
 
-    let mem_available: f32 = latest_data.values.get("mem_available").unwrap_or(&"0".to_string()).parse().unwrap();
-    let mem_total : f32 = latest_data.values.get("mem_total").unwrap_or(&"0".to_string()).parse().unwrap();
+    let mem_available: f32 = latest_data.values.get(keys.get("mem_available")).unwrap_or(&"0".to_string()).parse().unwrap();
+    let mem_total : f32 = latest_data.values.get(keys.get("mem_total")).unwrap_or(&"0".to_string()).parse().unwrap();
     let mem_used = mem_total - mem_available;
     let mem_used_percent = mem_used / mem_total;
 
@@ -138,7 +139,7 @@ pub fn draw_mem_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &Hash
     draw_meter_bar(&mut d, x + 80, y + 55, 390, 23, (mem_used_percent * 100.0) as i32, 100, (gradient_color_1, gradient_color_2), fonts);
 }
 
-pub fn draw_core_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &Vec<&SensorData>) {
+pub fn draw_core_panel<D: RaylibDraw>(mut d: &mut D, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &Vec<&SensorData>) {
 
     let gradient_color_1 = Color::new(0, 200, 0, 255);
     let gradient_color_2 = Color::new(0, 40, 0, 255);
@@ -164,7 +165,7 @@ pub fn draw_core_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &Has
     }
 }
 
-pub fn draw_net_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &HashMap<String, Font>, host_data: &Vec<&SensorData>, guest_data: &Vec<&SensorData>) {
+pub fn draw_net_panel<D: RaylibDraw>(mut d: &mut D, x: i32, y: i32, fonts: &HashMap<String, Font>, host_data: &Vec<&SensorData>, guest_data: &Vec<&SensorData>) {
 
     let xf = x as f32;
     let yf = y as f32;
@@ -204,7 +205,7 @@ pub fn draw_net_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &Hash
     draw_graphs(&mut d, x, y, fonts, &guest_data, guest_receive_gradient_color_1, guest_receive_gradient_color_2, guest_send_gradient_color_1, guest_send_gradient_color_2, Color::BLUE, Color::PURPLE);
 }
 
-fn draw_graphs(mut d: &mut &mut RaylibDrawHandle, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &&Vec<&SensorData>, receive_gradient_color_1: Color, receive_gradient_color_2: Color, send_gradient_color_1: Color, send_gradient_color_2: Color, send_color: Color, recv_color: Color) {
+fn draw_graphs<D: RaylibDraw>(d: &mut &mut D, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &&Vec<&SensorData>, receive_gradient_color_1: Color, receive_gradient_color_2: Color, send_gradient_color_1: Color, send_gradient_color_2: Color, send_color: Color, recv_color: Color) {
     if data.last().is_none() {
         return
     }
@@ -221,8 +222,8 @@ fn draw_graphs(mut d: &mut &mut RaylibDrawHandle, x: i32, y: i32, fonts: &HashMa
 
             let received_label = format!("{:.2} Mbit/s", bytes_to_mbit(received_bytes_per_sec));
             let sent_label = format!("{:.2} Mbit/s", bytes_to_mbit(sent_bytes_per_sec));
-            draw_meter_bar_with_label(&mut d, x + 10, y + 65, 225, 23, bytes_to_mbit(received_bytes_per_sec) as i32, 100, (receive_gradient_color_1, receive_gradient_color_2), fonts, received_label, 70.0, Color::WHITE);
-            draw_meter_bar_with_label(&mut d, x + 245, y + 65, 225, 23, bytes_to_mbit(sent_bytes_per_sec) as i32, 100, (send_gradient_color_1, send_gradient_color_2), fonts, sent_label, 70.0, Color::WHITE);
+            draw_meter_bar_with_label(d, x + 10, y + 65, 225, 23, bytes_to_mbit(received_bytes_per_sec) as i32, 100, (receive_gradient_color_1, receive_gradient_color_2), fonts, received_label, 70.0, Color::WHITE);
+            draw_meter_bar_with_label(d, x + 245, y + 65, 225, 23, bytes_to_mbit(sent_bytes_per_sec) as i32, 100, (send_gradient_color_1, send_gradient_color_2), fonts, sent_label, 70.0, Color::WHITE);
 
             let received_graph_values = &data.iter()
                 .map(|d| d.values.get(&network_received_key))
@@ -238,78 +239,78 @@ fn draw_graphs(mut d: &mut &mut RaylibDrawHandle, x: i32, y: i32, fonts: &HashMa
                 .map(|v| if v > 100.0 { 100.0 } else { v })
                 .collect();
 
-            draw_graph(&mut d, x + 10, y + 100, sent_graph_values, send_color);
-            draw_graph(&mut d, x + 10, y + 100, received_graph_values, recv_color);
+            draw_graph(d, x + 10, y + 100, sent_graph_values, send_color);
+            draw_graph(d, x + 10, y + 100, received_graph_values, recv_color);
         }
     }
 }
 
 fn bytes_to_mbit(bytes: i64) -> f32 { (bytes * 8) as f32 / 1000000.0 }
 
-pub fn draw_temp_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &Vec<&SensorData>) {
+pub fn draw_temp_panel<D: RaylibDraw>(d: &mut D, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &Vec<&SensorData>, keys: Keys) {
 
     let latest_data = data.last().unwrap();
 
-    let pump_temp: f32 = latest_data.values.get("pump_temp").unwrap_or(&"0".to_string()).parse().unwrap_or(0.0);
-    let front_intake_temp: f32 = latest_data.values.get("front_intake_temp").unwrap_or(&"0".to_string()).parse().unwrap_or(0.0);
-    let exhaust_temp: f32 = latest_data.values.get("exhaust_temp").unwrap_or(&"0".to_string()).parse().unwrap_or(0.0);
-    let ambient_temp: f32 = latest_data.values.get("ambient_temp").unwrap_or(&"0".to_string()).parse().unwrap_or(0.0);
+    let pump_temp: f32 = latest_data.values.get(keys.get("pump_temp")).unwrap_or(&"0".to_string()).parse().unwrap_or(0.0);
+    let front_intake_temp: f32 = latest_data.values.get(keys.get("front_intake_temp")).unwrap_or(&"0".to_string()).parse().unwrap_or(0.0);
+    let exhaust_temp: f32 = latest_data.values.get(keys.get("exhaust_temp")).unwrap_or(&"0".to_string()).parse().unwrap_or(0.0);
+    let ambient_temp: f32 = latest_data.values.get(keys.get("ambient_temp")).unwrap_or(&"0".to_string()).parse().unwrap_or(0.0);
 
     let xf = x as f32;
     let yf = y as f32;
 
     d.draw_text_ex(get_font(fonts, "calibri_40_bold"), "Temps", Vector2::new(xf + 10.0, yf + 10.0), 40.0, 0.0, Color::WHITE);
 
-    draw_temperature_gauge(&mut d, x + 150, y , pump_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
+    draw_temperature_gauge(d, x + 150, y , pump_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
     d.draw_text_ex(get_font(fonts, "calibri_20"), "Pump", Vector2::new(xf + 152.0, yf + 55.0), 20.0, 0.0, Color::WHITE);
 
-    draw_temperature_gauge(&mut d, x + 230, y , front_intake_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
+    draw_temperature_gauge(d, x + 230, y , front_intake_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
     d.draw_text_ex(get_font(fonts, "calibri_20"), "Intake", Vector2::new(xf + 232.0, yf + 55.0), 20.0, 0.0, Color::WHITE);
 
-    draw_temperature_gauge(&mut d, x + 310, y, exhaust_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
+    draw_temperature_gauge(d, x + 310, y, exhaust_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
     d.draw_text_ex(get_font(fonts, "calibri_20"), "Exhaust", Vector2::new(xf + 306.0, yf + 55.0), 20.0, 0.0, Color::WHITE);
 
-    draw_temperature_gauge(&mut d, x + 390, y, ambient_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
+    draw_temperature_gauge(d, x + 390, y, ambient_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
     d.draw_text_ex(get_font(fonts, "calibri_20"), "Ambient", Vector2::new(xf + 382.0, yf + 55.0), 20.0, 0.0, Color::WHITE);
 }
 
-pub fn draw_rpm_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &Vec<&SensorData>) {
+pub fn draw_rpm_panel<D: RaylibDraw>(d: &mut D, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &Vec<&SensorData>, keys: Keys) {
 
     let latest_data = data.last().unwrap();
 
-    let top_1 = latest_data.values.get("fan1_rpm").unwrap_or(&"0.0".to_string()).parse::<f32>().unwrap_or(0.0) as i32;
-    let top_2 = latest_data.values.get("fan2_rpm").unwrap_or(&"0.0".to_string()).parse::<f32>().unwrap_or(0.0) as i32;
-    let top_3 = latest_data.values.get("fan3_rpm").unwrap_or(&"0.0".to_string()).parse::<f32>().unwrap_or(0.0) as i32;
-    let front_1 = latest_data.values.get("fan4_rpm").unwrap_or(&"0.0".to_string()).parse::<f32>().unwrap_or(0.0) as i32;
-    let front_2 = latest_data.values.get("fan5_rpm").unwrap_or(&"0.0".to_string()).parse::<f32>().unwrap_or(0.0) as i32;
-    let pump = latest_data.values.get("pump_rpm").unwrap_or(&"0.0".to_string()).parse::<f32>().unwrap_or(0.0) as i32;
+    let top_1 = latest_data.values.get(keys.get("fan1_rpm")).unwrap_or(&"0.0".to_string()).parse::<f32>().unwrap_or(0.0) as i32;
+    let top_2 = latest_data.values.get(keys.get("fan2_rpm")).unwrap_or(&"0.0".to_string()).parse::<f32>().unwrap_or(0.0) as i32;
+    let top_3 = latest_data.values.get(keys.get("fan3_rpm")).unwrap_or(&"0.0".to_string()).parse::<f32>().unwrap_or(0.0) as i32;
+    let front_1 = latest_data.values.get(keys.get("fan4_rpm")).unwrap_or(&"0.0".to_string()).parse::<f32>().unwrap_or(0.0) as i32;
+    let front_2 = latest_data.values.get(keys.get("fan5_rpm")).unwrap_or(&"0.0".to_string()).parse::<f32>().unwrap_or(0.0) as i32;
+    let pump = latest_data.values.get(keys.get("pump_rpm")).unwrap_or(&"0.0".to_string()).parse::<f32>().unwrap_or(0.0) as i32;
 
     let xf = x as f32;
     let yf = y as f32;
 
     d.draw_text_ex(get_font(fonts, "calibri_40_bold"), "RPM", Vector2::new(xf + 10.0, yf + 10.0), 40.0, 0.0, Color::WHITE);
 
-    draw_rpm_gauge(&mut d, x + 100, y , top_1, 1500, get_font(fonts, "calibri_15"));
+    draw_rpm_gauge(d, x + 100, y , top_1, 1500, get_font(fonts, "calibri_15"));
 
-    draw_rpm_gauge(&mut d, x + 165, y , top_2, 1500, get_font(fonts, "calibri_15"));
+    draw_rpm_gauge(d, x + 165, y , top_2, 1500, get_font(fonts, "calibri_15"));
     d.draw_text_ex(get_font(fonts, "calibri_20"), "Top", Vector2::new(xf + 175.0, yf + 55.0), 20.0, 0.0, Color::WHITE);
 
-    draw_rpm_gauge(&mut d, x + 230, y, top_3, 1500, get_font(fonts, "calibri_15"));
+    draw_rpm_gauge(d, x + 230, y, top_3, 1500, get_font(fonts, "calibri_15"));
 
     d.draw_line(x + 100 , y + 54, x + 280, y + 54, Color::WHITE);
     d.draw_line(x + 100 , y + 54, x + 100, y + 49, Color::WHITE);
     d.draw_line(x + 280, y + 54, x + 280, y + 49, Color::WHITE);
 
-    draw_rpm_gauge(&mut d, x + 295, y, front_1, 2000, get_font(fonts, "calibri_15"));
+    draw_rpm_gauge(d, x + 295, y, front_1, 2000, get_font(fonts, "calibri_15"));
 
-    draw_rpm_gauge(&mut d, x + 360, y, front_2, 2000, get_font(fonts, "calibri_15"));
+    draw_rpm_gauge(d, x + 360, y, front_2, 2000, get_font(fonts, "calibri_15"));
     d.draw_text_ex(get_font(fonts, "calibri_20"), "Front", Vector2::new(xf + 333.0, yf + 55.0), 20.0, 0.0, Color::WHITE);
 
     d.draw_line(x + 295, y + 54, x + 410, y + 54, Color::WHITE);
     d.draw_line(x + 295, y + 54, x + 295, y + 49, Color::WHITE);
     d.draw_line(x + 410, y + 54, x + 410, y + 49, Color::WHITE);
 
-    draw_rpm_gauge(&mut d, x + 425, y, pump, 5000, get_font(fonts, "calibri_15"));
+    draw_rpm_gauge(d, x + 425, y, pump, 5000, get_font(fonts, "calibri_15"));
     d.draw_text_ex(get_font(fonts, "calibri_20"), "Pump", Vector2::new(xf + 428.0, yf + 55.0), 20.0, 0.0, Color::WHITE);
 
     d.draw_line(x + 425, y + 54, x + 475, y + 54, Color::WHITE);
@@ -317,7 +318,7 @@ pub fn draw_rpm_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &Hash
     d.draw_line(x + 475, y + 54, x + 475, y + 49, Color::WHITE);
 }
 
-pub fn draw_graph_grid(d: &mut &mut RaylibDrawHandle, x: i32, y: i32) {
+pub fn draw_graph_grid<D: RaylibDraw>(d: &mut &mut D, x: i32, y: i32) {
     let grid_color = Color::new(49, 50, 50, 255);
 
     d.draw_rectangle(x, y, 460, 80, Color::DARKGRAY);
@@ -332,7 +333,7 @@ pub fn draw_graph_grid(d: &mut &mut RaylibDrawHandle, x: i32, y: i32) {
     }
 }
 
-fn draw_graph(d: &mut &mut RaylibDrawHandle, x: i32, y: i32, historical: &Vec<f32>, color: Color) {
+fn draw_graph<D: RaylibDraw>(d: &mut &mut D, x: i32, y: i32, historical: &Vec<f32>, color: Color) {
 
     if historical.len() == 0 {
         return;
@@ -351,12 +352,12 @@ fn draw_graph(d: &mut &mut RaylibDrawHandle, x: i32, y: i32, historical: &Vec<f3
     }
 }
 
-pub fn draw_meter_bar(d: &mut &mut RaylibDrawHandle, x: i32, y: i32, width: i32, height: i32, value: i32, max_value: i32, color: (Color, Color), fonts: &HashMap<String, Font>) {
+pub fn draw_meter_bar<D: RaylibDraw>(d: &mut &mut D, x: i32, y: i32, width: i32, height: i32, value: i32, max_value: i32, color: (Color, Color), fonts: &HashMap<String, Font>) {
     let label = format!("{} %", value.to_string());
     draw_meter_bar_with_label(d, x, y, width, height, value, max_value, color, fonts, label, width as f32 / 2.0 - 15.0, Color::WHITE);
 }
 
-pub fn draw_meter_bar_with_label(d: &mut &mut RaylibDrawHandle, x: i32, y: i32, width: i32, height: i32, value: i32, max_value: i32, color: (Color, Color), fonts: &HashMap<String, Font>, label: String, label_pos: f32, label_color: Color) {
+pub fn draw_meter_bar_with_label<D: RaylibDraw>(d: &mut &mut D, x: i32, y: i32, width: i32, height: i32, value: i32, max_value: i32, color: (Color, Color), fonts: &HashMap<String, Font>, label: String, label_pos: f32, label_color: Color) {
     d.draw_rectangle(x, y, width, height, Color::DARKGRAY);
     d.draw_rectangle(x + 1, y + 1, width - 2, height - 2, Color::BLACK);
 
@@ -367,7 +368,7 @@ pub fn draw_meter_bar_with_label(d: &mut &mut RaylibDrawHandle, x: i32, y: i32,
     d.draw_text_ex(get_font(fonts, "calibri_20"), &label, Vector2::new(label_pos + x as f32, y as f32 + 3.0), 20.0, 0.0, label_color);
 }
 
-pub fn draw_temperature_gauge(d: &mut RaylibDrawHandle, x: i32, y: i32, value: i32, font: &Font, font2: &Font) {
+pub fn draw_temperature_gauge<D: RaylibDraw>(d: &mut D, x: i32, y: i32, value: i32, font: &Font, font2: &Font) {
     d.draw_circle(x + 25, y + 25, 25.0, Color::LIGHTGRAY);
     d.draw_circle(x + 25, y + 25, 23.0, Color::BLACK);
 
@@ -388,7 +389,7 @@ pub fn draw_temperature_gauge(d: &mut RaylibDrawHandle, x: i32, y: i32, value: i
     d.draw_text_ex(font2, "C", Vector2::new(x as f32 + 25.0, y as f32 + 34.0), 13.0, 0.0, degree_color);
 }
 
-pub fn draw_rpm_gauge(d: &mut RaylibDrawHandle, x: i32, y: i32, value: i32, max: i32, font: &Font) {
+pub fn draw_rpm_gauge<D: RaylibDraw>(d: &mut D, x: i32, y: i32, value: i32, max: i32, font: &Font) {
     let background = Color::new(0, 100, 20, 255);
     let background1 = Color::new(100, 100, 20, 255);
     let background2 = Color::new(100, 0, 20, 255);
@@ -1,22 +1,42 @@
 use crate::fonts::load_fonts;
 use crate::textures::load_textures;
-use std::{thread, process};
+use std::env;
+use std::process;
 use std::sync::{Arc, Mutex};
+use arc_swap::ArcSwap;
 use crate::config::{read_config};
 use clap::{App, Arg};
-use crate::screenctl::get_screen_control;
+use crate::screenctl::{CachedScreenControl, switch_screen};
 use std::time::{Duration, Instant};
-use crate::state::{StateExt, State, Action};
+use std::sync::mpsc::Sender;
+use crate::state::{StateExt, State, Action, Severity, CarouselState};
+use crate::reload::ConfigStore;
+use crate::websocket::SensorReport;
 use raylib::core::drawing::RaylibDraw;
 use raylib::color::Color;
+use raylib::{RaylibHandle, RaylibThread};
+use raylib::core::text::Font;
+use raylib::core::texture::{RaylibRenderTexture2D, RaylibTexture2D, RenderTexture2D, Texture2D};
+use raylib::consts::TextureFilter;
+use raylib::drawing::RaylibTextureModeExt;
+use std::collections::HashMap;
+use chrono::NaiveDateTime;
+use crate::config::SweepMode;
+use raylib::consts::MouseButton;
 use crate::websocket::{WebSocket, WebSocketExt};
-use pending_panel::PendingPanel;
-use crate::windows_panel::WindowsPanel;
-use crate::panel::Panel;
-use crate::linux_panel::LinuxPanel;
+use crate::panel::{panel_names, select_panel, Panel, PanelKind, Panels};
+use crate::layout::load_layouts;
 use crate::log::{Log, LogExt, LogLevel};
 use crate::context::Context;
+use crate::redraw::Redraw;
 use crate::event::{Event, EventExt};
+use crate::clock::{Clock, SystemClock};
+use crate::common_widgets::{draw_notifications, draw_dim_overlay, draw_sweep, draw_screensaver, draw_page_indicator};
+use crate::burnin::LayoutTracker;
+use crate::viewport::Viewport;
+use crate::render::{RenderCtx, Theme};
+use crate::sensors::Sensors;
+use raylib::drawing::RaylibMode2DExt;
 
 mod config;
 mod fonts;
@@ -24,9 +44,8 @@ mod textures;
 mod common_widgets;
 mod windows_widgets;
 mod linux_widgets;
-mod windows_panel;
 mod pending_panel;
-mod linux_panel;
+mod overview_panel;
 mod data;
 mod screenctl;
 mod state;
@@ -35,14 +54,44 @@ mod panel;
 mod log;
 mod context;
 mod event;
+mod presence;
+mod schedule;
+mod idle;
+mod clock;
+mod redraw;
+mod brightness;
+mod fade;
+mod burnin;
+mod reload;
+mod config_check;
+mod overrides;
+mod window;
+mod layout;
+mod layout_panel;
+mod viewport;
+mod render;
+mod sensors;
+#[cfg(test)]
+mod snapshot_bench;
+
+const INPUT_REDRAW_SECS: u64 = 1;
+const RELOAD_ERROR_TTL_SECS: u32 = 30;
 
 fn main() {
+    let override_flags = overrides::cli_flags();
     #[link(name="libray", kind="dylib")]
     let matches = App::new("Sensor Panel")
         .args(&[Arg::new("configpath")
             .short('c')
             .long("configfile")
-            .takes_value(true)])
+            .takes_value(true)
+            .global(true),
+            Arg::new("print-config")
+            .long("print-config")
+            .help("Prints the effective config after applying environment and command line overrides")])
+        .args(overrides::cli_args(&override_flags))
+        .subcommand(App::new("check-config")
+            .about("Checks the config file and referenced resources, exiting non-zero on errors"))
         .get_matches();
 
     let config_path = match matches.value_of("configpath") {
@@ -50,82 +99,318 @@ fn main() {
         Some(s) => s
     };
 
-    let config = read_config(config_path);
+    if matches.subcommand_matches("check-config").is_some() {
+        process::exit(config_check::run(config_path));
+    }
+
+    // Command line flags come last so they win over SENSORPANEL_* variables, which win over the file
+    let config_overrides = [overrides::from_env(env::vars()), overrides::from_args(&matches)].concat();
+    let config = read_config(config_path, &config_overrides);
+
+    if matches.is_present("print-config") {
+        print!("{}", overrides::print_config(&config));
+        process::exit(0);
+    }
+    let fps = config.fps;
+
+    let (mut handle, thread) = window::open_window(&config.window);
 
-    let (mut handle, thread) = raylib::init()
-        .size(1024, 600)
-        .title("SensorPanel")
-        .build();
+    handle.set_target_fps(fps);
 
-    handle.set_target_fps(config.fps);
+    let viewport = Viewport::fit(handle.get_screen_width(), handle.get_screen_height(), config.window.rotation);
+    let fonts = load_fonts(&mut handle, &thread, &config.resources, viewport.scale);
+    let textures = load_textures(&mut handle, &thread, &config.resources, viewport.is_scaled() || viewport.is_rotated());
+    let render_target = render_target(&mut handle, &thread, &viewport);
+    let layouts = load_layouts(&config.resources);
+    let clock: Arc<dyn Clock> = Arc::new(SystemClock());
+    let now = clock.now();
+    let state = Arc::new(ArcSwap::from_pointee(State::init(now)));
 
-    let fonts = load_fonts(&mut handle, &thread, &config.resources);
-    let textures = load_textures(&mut handle, &thread, &config.resources);
-    let state = Arc::new(Mutex::new(State::init()));
+    let config_store = Arc::new(ConfigStore::new(config_path, &config_overrides, config));
+    let panels = Panels::build(&config_store.load(), &layouts);
+    let screen_control = Arc::new(Mutex::new(CachedScreenControl::build(&config_store.load())));
 
     let mut context = Context {
-        config,
+        config: config_store,
         thread,
         handle,
         fonts,
         textures,
-        state
+        layouts,
+        panels,
+        theme: Theme::default(),
+        screen_control,
+        state,
+        clock,
+        redraw: Redraw::new(),
+        layout: LayoutTracker::new(now),
+        viewport,
+        render_target,
+        last_frame: now
     };
 
-    event_receiver_setup(&context);
+    let input = event_receiver_setup(&context);
+
+    let mut target_fps = fps;
 
     while !context.handle.window_should_close() {
+        let fps = context.config.load().fps;
+        if fps != target_fps {
+            context.handle.set_target_fps(fps);
+            target_fps = fps;
+        }
+
+        update_viewport(&mut context);
         draw_window(&mut context);
+
+        if context.handle.is_mouse_button_pressed(MouseButton::MOUSE_LEFT_BUTTON) && !context.config.load().carousel_pages.is_empty() {
+            toggle_carousel(&input);
+        }
+
+        if has_input(&mut context.handle) {
+            context.redraw.animate_for(Duration::from_secs(INPUT_REDRAW_SECS), context.clock.now());
+            context.layout.reset(context.clock.now());
+        }
+
+        context.redraw.wait(context.clock.now(), context.clock.local_now());
+    }
+}
+
+fn has_input(handle: &mut RaylibHandle) -> bool {
+    handle.get_key_pressed().is_some()
+        || handle.is_mouse_button_down(MouseButton::MOUSE_LEFT_BUTTON)
+        || handle.get_touch_points_count() > 0
+}
+
+// Taps on the panel pause and resume the carousel, sent to the event loop like any other action.
+fn toggle_carousel(input: &Sender<SensorReport>) {
+    let _ = input.send(SensorReport {
+        reporter: "sensorpanel".to_string(),
+        topic: "actions".to_string(),
+        sensors: vec![("toggle_carousel".to_string(), String::new())].into_iter().collect()
+    });
+}
+
+// Refits the virtual layout when the window size or rotation changes, reloading fonts at the new
+// scale and recreating the render target for rotated frames.
+fn update_viewport(context: &mut Context) {
+    let (width, height) = (context.handle.get_screen_width(), context.handle.get_screen_height());
+    let rotation = context.config.load().window.rotation;
+    let current = &context.viewport;
+    if (width, height, rotation) == (current.screen_width, current.screen_height, current.rotation) {
+        return;
+    }
+
+    let viewport = Viewport::fit(width, height, rotation);
+    Log::log(LogLevel::DEBUG, &*format!("Window {}x{} rotated {}, {:?} layout at scale {:.3}",
+                                        width, height, rotation, viewport.orientation(), viewport.scale));
+
+    if viewport.scale != context.viewport.scale {
+        let resources = context.config.load().resources.clone();
+        context.fonts = load_fonts(&mut context.handle, &context.thread, &resources, viewport.scale);
+    }
+    context.render_target = render_target(&mut context.handle, &context.thread, &viewport);
+    context.viewport = viewport;
+}
+
+// Rotated frames are drawn to a texture that is then drawn rotated to the window.
+fn render_target(handle: &mut RaylibHandle, thread: &RaylibThread, viewport: &Viewport) -> Option<RenderTexture2D> {
+    if !viewport.is_rotated() {
+        return None;
     }
+
+    let (width, height) = viewport.target_size();
+    let target = handle.load_render_texture(thread, width as u32, height as u32)
+        .unwrap_or_else(|error| panic!("Failed to create {}x{} render target: {}", width, height, error));
+    target.texture().set_texture_filter(thread, TextureFilter::TEXTURE_FILTER_BILINEAR);
+
+    return Some(target);
+}
+
+// Everything a frame is drawn from, drawn either to the window or to the rotated render target.
+struct Frame<'a> {
+    fonts: &'a HashMap<String, Font>,
+    textures: &'a HashMap<String, Texture2D>,
+    theme: &'a Theme,
+    clock: &'a dyn Clock,
+    state: &'a State,
+    carousel: Option<&'a CarouselState>,
+    viewport: Viewport,
+    shift: (i32, i32),
+    screensaver: bool,
+    sweep: Option<(&'a SweepMode, f32)>,
+    dim_level: u8,
+    local_now: NaiveDateTime,
+    now: Instant
+}
+
+fn draw_frame<D: RaylibDraw>(d: &mut D, frame: &Frame, panel: &mut PanelKind) {
+    let viewport = frame.viewport;
+    let size = (viewport.width, viewport.height);
+    d.clear_background(Color::BLACK);
+
+    if frame.screensaver {
+        let mut scaled = d.begin_mode2D(viewport.camera((0, 0)));
+        draw_screensaver(&mut scaled, frame.fonts, frame.local_now, size);
+    } else {
+        let layout = panel.layout(viewport.orientation());
+        let panel_viewport = viewport.with_orientation(layout);
+        {
+            let sensors = Sensors::index(&frame.state.sensor_data);
+            let mut shifted = d.begin_mode2D(panel_viewport.camera(frame.shift));
+            let mut ctx = RenderCtx {
+                d: &mut shifted,
+                fonts: frame.fonts,
+                textures: frame.textures,
+                theme: frame.theme,
+                clock: frame.clock,
+                sensors: &sensors,
+                layout
+            };
+            panel.draw(&mut ctx);
+            if let Some(carousel) = frame.carousel {
+                draw_page_indicator(ctx.d, carousel, layout.virtual_size());
+            }
+        }
+
+        // Panels clear the whole target, so the bars around the virtual space are blanked again
+        for (x, y, width, height) in panel_viewport.bars() {
+            d.draw_rectangle(x, y, width, height, Color::BLACK);
+        }
+    }
+
+    let mut scaled = d.begin_mode2D(viewport.camera((0, 0)));
+    draw_notifications(&mut scaled, frame.fonts, &frame.state.notifications, frame.now, viewport.width);
+    if let Some((mode, progress)) = frame.sweep {
+        draw_sweep(&mut scaled, mode, progress, size);
+    }
+    draw_dim_overlay(&mut scaled, frame.dim_level, size);
 }
 
 fn draw_window(context: &mut Context) {
-    if context.state.lock().unwrap().screen_on {
+    let state = context.state.load();
+    let config = context.config.load();
+    let now = context.clock.now();
+    let dt = now.saturating_duration_since(context.last_frame);
+    let fading = fade::remaining(&state, &config.transitions, now);
+    context.last_frame = now;
+
+    if !context.panels.is_built_from(&config) {
+        context.panels = Panels::build(&config, &context.layouts);
+    }
+    let (hardware_brightness, clear_screen) = {
+        let mut screen_control = context.screen_control.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let control = screen_control.get(&config);
+        (control.has_hardware_brightness(), control.should_clear_screen())
+    };
+
+    if let Some(remaining) = fading {
+        context.redraw.animate_for(remaining, now);
+    }
+
+    if state.screen_on || fading.is_some() {
+        let visibility = fade::visibility(&state, &config.transitions, now) as u32;
+        let local_now = context.clock.local_now();
+        let burn_in = config.burn_in.as_ref();
+        let panel = select_panel(&state);
+        context.layout.observe(&panel, now);
+        let active_panel = context.panels.get(&panel);
+        active_panel.update(&state, dt);
+
+        let sweep = burn_in.and_then(|b| burnin::sweep(b, local_now));
+        if let Some((_, remaining)) = sweep {
+            context.redraw.animate_for(remaining, now);
+        }
+
+        let layout = &context.layout;
+        let frame = Frame {
+            fonts: &context.fonts,
+            textures: &context.textures,
+            theme: &context.theme,
+            clock: context.clock.as_ref(),
+            state: &state,
+            carousel: state.carousel.as_ref().filter(|_| config.carousel.indicator && state.panel_override.is_none()),
+            viewport: context.viewport,
+            shift: burn_in.map_or((0, 0), |b| burnin::pixel_shift(b, local_now)),
+            screensaver: burn_in.is_some_and(|b| burnin::screensaver_active(b, layout, now)),
+            sweep: burn_in.zip(sweep).map(|(b, (progress, _))| (&b.sweep_mode, progress)),
+            dim_level: if hardware_brightness {
+                visibility as u8
+            } else {
+                (state.brightness as u32 * visibility / 100) as u8
+            },
+            local_now,
+            now
+        };
+
         let mut d = context.handle.begin_drawing(&context.thread);
-        let now = Instant::now();
-
-        let has_windows_data = context.state.lock().unwrap().sensor_data.iter()
-            .filter(|d| { d.reporter == "windows-sensor-agent" })
-            .filter(|d| { now - d.received < Duration::from_secs(10) })
-            .count() > 0;
-
-        let has_linux_data = context.state.lock().unwrap().sensor_data.iter()
-            .filter(|d| { d.reporter == "linux-sensor-agent" })
-            .filter(|d| { now - d.received < Duration::from_secs(10) })
-            .count() > 0;
-
-        if has_windows_data {
-            WindowsPanel::draw(&context.fonts, &context.textures, &mut d, &(context.state.lock().unwrap().sensor_data));
-        } else if has_linux_data {
-            LinuxPanel::draw(&context.fonts, &context.textures, &mut d, &(context.state.lock().unwrap().sensor_data));
-        } else {
-            PendingPanel::draw(&context.fonts, &context.textures, &mut d, &(context.state.lock().unwrap().sensor_data));
+        match context.render_target.as_mut() {
+            Some(target) => {
+                draw_frame(&mut d.begin_texture_mode(&context.thread, target), &frame, active_panel);
+                let (source, dest, origin) = frame.viewport.present();
+                d.clear_background(Color::BLACK);
+                d.draw_texture_pro(target.texture(), source, dest, origin, frame.viewport.rotation as f32, Color::WHITE);
+            }
+            None => draw_frame(&mut d, &frame, active_panel)
         }
     } else {
-        if get_screen_control().should_clear_screen() {
-            let mut d = context.handle.begin_drawing(&context.thread);
+        // A frame is ended even when hardware keeps the screen off, as that's where input is polled
+        let mut d = context.handle.begin_drawing(&context.thread);
+        if clear_screen {
             d.clear_background(Color::BLACK);
-        } else {
-            thread::sleep(Duration::from_secs(1));
         }
     }
 }
 
-fn event_receiver_setup(context: &Context) {
-    WebSocket::receiver_loop(&context, |event, state, config| {
-        let new_state = Event::handle(event, state, config);
+fn reload_config(config_store: &Arc<ConfigStore>, state: &State, now: Instant, feedback: &Sender<SensorReport>) -> State {
+    match config_store.reload() {
+        Ok(previous) => {
+            let config = config_store.load();
+            Log::log(LogLevel::INFO, "Config reloaded");
+
+            if config.relay_host != previous.relay_host {
+                Log::log(LogLevel::INFO, &*format!("Relay host changed to {}, reconnecting", config.relay_host));
+                WebSocket::relay_setup(config_store.clone(), feedback.clone());
+            }
+
+            state.clone()
+        }
+        Err(error) => {
+            Log::log(LogLevel::ERROR, &*format!("Config reload failed, keeping previous config: {}", error));
+            state.push_notification("Config reload failed", &error, Severity::ERROR, RELOAD_ERROR_TTL_SECS, now)
+        }
+    }
+}
+
+fn event_receiver_setup(context: &Context) -> Sender<SensorReport> {
+    let screen_control = context.screen_control.clone();
+    let panels = panel_names(&context.layouts);
+
+    WebSocket::receiver_loop(&context, move |event, state, config_store, clock, feedback| {
+        let config = config_store.load();
+        let mut new_state = Event::handle(event, state, &config, &panels, clock);
 
         for action in new_state.state_change_actions(state) {
             match action {
-                Action::ScreenOn => get_screen_control().turn_on(),
-                Action::ScreenOff => get_screen_control().turn_off()
+                Action::ScreenOn => switch_screen(&config, true, Duration::from_secs(0), feedback.clone()),
+                Action::ScreenOff => switch_screen(&config, false, Duration::from_millis(config.transitions.fade_out_ms), feedback.clone()),
+                Action::SetBrightness(level) => {
+                    let mut screen_control = screen_control.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                    if !screen_control.get(&config).set_brightness(level) {
+                        Log::log(LogLevel::DEBUG, &*format!("Brightness set to {}% using software dimming", level));
+                    }
+                }
+                Action::ReloadConfig => {
+                    new_state = reload_config(config_store, &new_state, clock.now(), feedback);
+                }
+                _ => {}
             };
         }
 
-        new_state.transfer_to(state);
+        new_state
     },
     |error| {
         Log::log(LogLevel::ERROR, &*format!("Got error {}", error));
         process::exit(1);
-    });
+    })
 }
@@ -0,0 +1,201 @@
+use clap::{Arg, ArgMatches};
+use crate::config::{Config, CONFIG_KEYS, CONFIG_ARRAYS};
+use crate::config_check::check_values;
+use crate::log::{Log, LogExt, LogLevel};
+use toml::Value;
+
+const ENV_PREFIX: &str = "SENSORPANEL_";
+
+// A single field set from the environment or the command line, applied on top of the config file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Override {
+    pub table: String,
+    pub key: String,
+    pub value: String,
+    pub origin: String
+}
+
+// Every overridable field as (table, key), "" being the top level. Tables are overridden key
+// by key while arrays of tables such as schedules are replaced as a whole by an inline TOML array.
+pub fn fields() -> Vec<(&'static str, &'static str)> {
+    let is_table = |key: &str| CONFIG_KEYS.iter().any(|(table, _)| *table == key);
+
+    CONFIG_KEYS.iter()
+        .filter(|(table, _)| !CONFIG_ARRAYS.contains(table))
+        .flat_map(|(table, keys)| keys.iter().map(move |key| (*table, *key)))
+        .filter(|(table, key)| !table.is_empty() || !is_table(key) || CONFIG_ARRAYS.contains(key))
+        .collect()
+}
+
+pub fn flag_name(table: &str, key: &str) -> String {
+    join(table, key, "-").replace('_', "-")
+}
+
+pub fn env_name(table: &str, key: &str) -> String {
+    format!("{}{}", ENV_PREFIX, join(table, key, "_").to_uppercase())
+}
+
+fn join(table: &str, key: &str, separator: &str) -> String {
+    if table.is_empty() { key.to_string() } else { format!("{}{}{}", table, separator, key) }
+}
+
+// Flag names and help texts for cli_args, kept by the caller for as long as the clap App lives.
+pub fn cli_flags() -> Vec<(String, String)> {
+    fields().iter()
+        .map(|(table, key)| (flag_name(table, key), format!("Overrides {} from the config file (env {})", join(table, key, "."), env_name(table, key))))
+        .collect()
+}
+
+pub fn cli_args(flags: &[(String, String)]) -> Vec<Arg<'_>> {
+    flags.iter()
+        .map(|(flag, help)| Arg::new(flag.as_str()).long(flag.as_str()).takes_value(true).value_name("VALUE").help(help.as_str()))
+        .collect()
+}
+
+pub fn from_args(matches: &ArgMatches) -> Vec<Override> {
+    fields().iter()
+        .filter_map(|(table, key)| {
+            let flag = flag_name(table, key);
+            matches.value_of(flag.as_str()).map(|value| Override {
+                table: table.to_string(),
+                key: key.to_string(),
+                value: value.to_string(),
+                origin: format!("--{}", flag)
+            })
+        })
+        .collect()
+}
+
+pub fn from_env(vars: impl Iterator<Item=(String, String)>) -> Vec<Override> {
+    let fields = fields();
+
+    vars.filter(|(name, _)| name.starts_with(ENV_PREFIX))
+        .filter_map(|(name, value)| {
+            let field = fields.iter().find(|(table, key)| env_name(table, key) == name);
+            if field.is_none() {
+                Log::log(LogLevel::INFO, &*format!("Ignoring unknown config variable {}", name));
+            }
+
+            field.map(|(table, key)| Override {
+                table: table.to_string(),
+                key: key.to_string(),
+                value: value.clone(),
+                origin: name.clone()
+            })
+        })
+        .collect()
+}
+
+// Applies overrides in order so later ones win. Values are parsed as TOML and taken as plain
+// strings when that doesn't fit the field, so both --fps 5 and --relay-host relay.local work.
+pub fn apply(config_str: &str, overrides: &[Override]) -> Result<Config, String> {
+    let mut merged: Value = toml::from_str(config_str).map_err(|error| error.to_string())?;
+
+    for item in overrides.iter() {
+        let typed = toml::from_str::<Value>(&format!("value = {}", item.value)).ok()
+            .and_then(|parsed| parsed.get("value").cloned());
+        let candidates = typed.into_iter().chain(std::iter::once(Value::String(item.value.clone())));
+
+        let accepted = candidates
+            .map(|value| with_field(&merged, item, value))
+            .find(|candidate| candidate.clone().try_into::<Config>().is_ok());
+
+        merged = accepted.ok_or(format!("{}: invalid value '{}' for {}", item.origin, item.value, join(&item.table, &item.key, ".")))?;
+    }
+
+    let config: Config = merged.try_into().map_err(|error| error.to_string())?;
+    let problems: Vec<String> = check_values(&config).iter()
+        .map(|(path, message)| format!("{}: {}", path, message))
+        .collect();
+
+    if problems.is_empty() { Ok(config) } else { Err(problems.join("; ")) }
+}
+
+fn with_field(config: &Value, item: &Override, value: Value) -> Value {
+    let mut config = config.clone();
+    let root = config.as_table_mut().expect("config is a table");
+
+    if item.table.is_empty() {
+        root.insert(item.key.clone(), value);
+    } else {
+        let table = root.entry(item.table.clone()).or_insert_with(|| Value::Table(Default::default()));
+        if let Some(table) = table.as_table_mut() {
+            table.insert(item.key.clone(), value);
+        }
+    }
+
+    config
+}
+
+pub fn print_config(config: &Config) -> String {
+    Value::try_from(config)
+        .and_then(|value| toml::to_string_pretty(&value))
+        .unwrap_or_else(|error| format!("# Failed to print config: {}\n", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::App;
+    use std::collections::HashSet;
+
+    fn env(vars: Vec<(&str, &str)>) -> Vec<Override> {
+        from_env(vars.into_iter().map(|(k, v)| (k.to_string(), v.to_string())))
+    }
+
+    #[test]
+    fn names_are_unique_for_every_field() {
+        let fields = fields();
+        let flags: HashSet<String> = fields.iter().map(|(t, k)| flag_name(t, k)).collect();
+        let envs: HashSet<String> = fields.iter().map(|(t, k)| env_name(t, k)).collect();
+
+        assert_eq!(flags.len(), fields.len());
+        assert_eq!(envs.len(), fields.len());
+        assert!(flags.contains("screen-control-backend"));
+        assert!(envs.contains("SENSORPANEL_RELAY_HOST"));
+        assert!(flags.contains("schedules"));
+        assert!(!flags.contains("screen-control"));
+    }
+
+    #[test]
+    fn applies_typed_and_string_values() {
+        let overrides = env(vec![("SENSORPANEL_FPS", "5"), ("SENSORPANEL_RELAY_HOST", "relay.local"),
+                                 ("SENSORPANEL_USB_POWER_PORTS", "2"), ("SENSORPANEL_SCREEN_CONTROL_BACKEND", "xset"),
+                                 ("SENSORPANEL_SCHEDULES", "[{ start = \"23:00\", end = \"07:00\", mode = \"off\" }]"),
+                                 ("SENSORPANEL_UNKNOWN", "1"), ("PATH", "/usr/bin")]);
+        let config = apply("fps = 1\nrelay_host = 'file'", &overrides).unwrap();
+
+        assert_eq!(overrides.len(), 5);
+        assert_eq!(config.fps, 5);
+        assert_eq!(config.relay_host, "relay.local");
+        assert_eq!(config.usb_power.unwrap().ports.as_deref(), Some("2"));
+        assert_eq!(config.screen_control.backend, crate::config::ScreenBackend::XSET);
+        assert_eq!(config.schedules.len(), 1);
+    }
+
+    #[test]
+    fn command_line_wins_over_environment() {
+        let flags = cli_flags();
+        let matches = App::new("test").args(cli_args(&flags)).get_matches_from(vec!["test", "--fps", "30"]);
+        let overrides = [env(vec![("SENSORPANEL_FPS", "10")]), from_args(&matches)].concat();
+
+        assert_eq!(apply("fps = 1", &overrides).unwrap().fps, 30);
+        assert_eq!(apply("fps = 1", &[]).unwrap().fps, 1);
+    }
+
+    #[test]
+    fn rejects_invalid_overrides() {
+        assert_eq!(apply("", &env(vec![("SENSORPANEL_FPS", "fast")])).unwrap_err(), "SENSORPANEL_FPS: invalid value 'fast' for fps");
+        assert_eq!(apply("", &env(vec![("SENSORPANEL_FPS", "0")])).unwrap_err(), "fps: must be at least 1");
+    }
+
+    #[test]
+    fn prints_effective_config() {
+        let config = apply("fps = 1", &env(vec![("SENSORPANEL_TRANSITIONS_FADE_IN_MS", "250")])).unwrap();
+        let printed = print_config(&config);
+
+        assert!(printed.contains("fps = 1\n"), "{}", printed);
+        assert!(printed.contains("[transitions]\nfade_in_ms = 250\n"), "{}", printed);
+        assert_eq!(toml::from_str::<Config>(&printed).unwrap().transitions.fade_in_ms, 250);
+    }
+}
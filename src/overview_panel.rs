@@ -0,0 +1,267 @@
+use raylib::core::drawing::RaylibDraw;
+use raylib::color::Color;
+use raylib::prelude::Vector2;
+use std::time::{Duration, Instant};
+use crate::config::Config;
+use crate::panel::Panel;
+use crate::render::{RenderCtx, Theme};
+use crate::sensors::Sensors;
+use crate::state::State;
+use crate::viewport::Orientation;
+
+const SPARKLINE_POINTS: usize = 60;
+const MARGIN: i32 = 16;
+const CARD_GAP: i32 = 12;
+
+// A card per host, a host being any reporter that sends cpu_utilization. The grid grows with the
+// number of active hosts. Hosts without a report within the panel selection staleness window are
+// shown as offline after the online ones, and left out once offline for overview.drop_offline_secs.
+pub(crate) struct OverviewPanel {
+    offline_after: Duration,
+    drop_after: Duration,
+    hosts: Vec<HostSummary>,
+    summarized: Option<(usize, Instant)>
+}
+
+#[derive(Debug, PartialEq)]
+struct HostSummary {
+    name: String,
+    last_report: Instant,
+    cpu: Option<f32>,
+    gpu: Option<f32>,
+    hottest: Option<(String, f32)>,
+    history: Vec<f32>
+}
+
+impl OverviewPanel {
+    pub fn new(config: &Config) -> OverviewPanel {
+        OverviewPanel {
+            offline_after: Duration::from_secs(config.panel_selection.stale_secs),
+            drop_after: Duration::from_secs(config.overview.drop_offline_secs),
+            hosts: Vec::new(),
+            summarized: None
+        }
+    }
+}
+
+impl Panel for OverviewPanel {
+    // Hosts are only summarized again when a report has arrived since the last frame.
+    fn update(&mut self, snapshot: &State, _dt: Duration) {
+        let latest = snapshot.sensor_data.last().map(|report| (snapshot.sensor_data.len(), report.received));
+        if latest != self.summarized {
+            self.hosts = host_summaries(&Sensors::index(&snapshot.sensor_data));
+            self.summarized = latest;
+        }
+    }
+
+    fn draw<D: RaylibDraw>(&mut self, ctx: &mut RenderCtx<D>) {
+        let (width, height) = ctx.layout.virtual_size();
+        ctx.d.clear_background(ctx.theme.background);
+
+        let hosts = active_hosts(&self.hosts, ctx.clock.now(), self.offline_after, self.drop_after);
+        if hosts.is_empty() {
+            ctx.text("calibri_30", "No hosts reporting", (width / 2 - 110, height / 2 - 15), ctx.theme.muted);
+            return;
+        }
+
+        for ((host, online), card) in hosts.iter().zip(card_rects(hosts.len(), (width, height))) {
+            draw_card(ctx, host, *online, card);
+        }
+    }
+
+    fn layout(&self, orientation: Orientation) -> Orientation {
+        orientation
+    }
+}
+
+// Hosts to draw with whether they are online, online hosts first. Hosts that have been offline
+// for drop_after are left out.
+fn active_hosts(hosts: &[HostSummary], now: Instant, offline_after: Duration, drop_after: Duration) -> Vec<(&HostSummary, bool)> {
+    let mut active: Vec<(&HostSummary, bool)> = hosts.iter()
+        .map(|host| (host, now.saturating_duration_since(host.last_report)))
+        .filter(|(_, silent)| *silent < offline_after + drop_after)
+        .map(|(host, silent)| (host, silent < offline_after))
+        .collect();
+    active.sort_by_key(|(_, online)| !*online);
+
+    active
+}
+
+// Columns and rows for count cards, the longer side of the panel getting the extra column.
+fn grid(count: usize, (width, height): (i32, i32)) -> (usize, usize) {
+    let mut long = 1;
+    while long * long < count {
+        long += 1;
+    }
+    let short = count.div_ceil(long);
+
+    if width >= height { (long, short) } else { (short, long) }
+}
+
+// Card positions as (x, y, width, height), filled row by row.
+fn card_rects(count: usize, (width, height): (i32, i32)) -> Vec<(i32, i32, i32, i32)> {
+    let (columns, rows) = grid(count, (width, height));
+    let card_width = (width - 2 * MARGIN - (columns as i32 - 1) * CARD_GAP) / columns as i32;
+    let card_height = (height - 2 * MARGIN - (rows as i32 - 1) * CARD_GAP) / rows as i32;
+
+    (0..count).map(|index| {
+        let (column, row) = ((index % columns) as i32, (index / columns) as i32);
+        (MARGIN + column * (card_width + CARD_GAP), MARGIN + row * (card_height + CARD_GAP), card_width, card_height)
+    }).collect()
+}
+
+fn host_summaries(sensors: &Sensors) -> Vec<HostSummary> {
+    sensors.reporters().into_iter().filter_map(|reporter| {
+        let reports = sensors.history(reporter);
+        if !reports.iter().any(|report| report.values.contains_key("cpu_utilization")) {
+            return None;
+        }
+
+        let latest = sensors.latest(reporter)?;
+        let hottest = latest.values.keys()
+            .filter(|key| key.ends_with("_temp"))
+            .filter_map(|key| sensors.number(reporter, key).map(|value| (key.trim_end_matches("_temp").replace('_', " "), value)))
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        let history = reports.iter()
+            .filter_map(|report| report.values.get("cpu_utilization").and_then(|value| value.parse::<f32>().ok()))
+            .collect::<Vec<f32>>();
+
+        Some(HostSummary {
+            name: reporter.trim_end_matches("-sensor-agent").to_string(),
+            last_report: latest.received,
+            cpu: sensors.number(reporter, "cpu_utilization"),
+            gpu: sensors.number(reporter, "gpu_utilization"),
+            hottest,
+            history: history[history.len().saturating_sub(SPARKLINE_POINTS)..].to_vec()
+        })
+    }).collect()
+}
+
+fn percent(value: Option<f32>) -> String {
+    value.map_or("-".to_string(), |value| format!("{:.0}%", value))
+}
+
+fn draw_card<D: RaylibDraw>(ctx: &mut RenderCtx<D>, host: &HostSummary, online: bool, (x, y, width, height): (i32, i32, i32, i32)) {
+    let theme: Theme = *ctx.theme;
+    let text_color = if online { theme.text } else { theme.muted };
+
+    ctx.d.draw_rectangle(x, y, width, height, theme.card);
+    ctx.d.draw_rectangle_lines(x, y, width, height, theme.border);
+
+    ctx.text("calibri_25_bold", &host.name, (x + 12, y + 10), text_color);
+    let (status, status_color) = if online { ("online", Color::GREEN) } else { ("offline", Color::RED) };
+    ctx.d.draw_circle(x + width - 80, y + 22, 5.0, status_color);
+    ctx.text("calibri_20", status, (x + width - 70, y + 12), text_color);
+
+    ctx.text("calibri_20", &format!("CPU {}", percent(host.cpu)), (x + 12, y + 45), text_color);
+    ctx.text("calibri_20", &format!("GPU {}", percent(host.gpu)), (x + width / 2, y + 45), text_color);
+
+    if let Some((name, value)) = &host.hottest {
+        let color = match value {
+            _ if !online => theme.muted,
+            v if *v > 80.0 => Color::RED,
+            v if *v > 70.0 => Color::ORANGE,
+            _ => Color::GREEN
+        };
+        ctx.text("calibri_20", &format!("{:.0}", value), (x + 12, y + 72), color);
+        ctx.d.draw_circle_lines(x + 38, y + 76, 2.05, color);
+        ctx.text("calibri_20", &format!("C {}", name), (x + 42, y + 72), text_color);
+    }
+
+    draw_sparkline(ctx.d, &host.history, (x + 12, y + 102, width - 24, height - 114), if online { theme.accent } else { theme.border });
+}
+
+// CPU utilization history scaled from 0 to 100% over the area, oldest report on the left.
+fn draw_sparkline<D: RaylibDraw>(d: &mut D, values: &[f32], (x, y, width, height): (i32, i32, i32, i32), color: Color) {
+    if values.len() < 2 || height <= 0 {
+        return;
+    }
+
+    let step = width as f32 / (SPARKLINE_POINTS - 1) as f32;
+    let start = x as f32 + width as f32 - step * (values.len() - 1) as f32;
+    let point = |index: usize, value: f32| {
+        Vector2::new(start + step * index as f32, (y + height) as f32 - value.clamp(0.0, 100.0) / 100.0 * height as f32)
+    };
+
+    for (index, pair) in values.windows(2).enumerate() {
+        d.draw_line_v(point(index, pair[0]), point(index + 1, pair[1]), color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::SensorData;
+    use crate::clock::{Clock, ManualClock};
+    use crate::state::StateExt;
+    use std::sync::Arc;
+
+    fn report(reporter: &str, values: Vec<(&str, &str)>, received: Instant) -> Arc<SensorData> {
+        let values = values.into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        Arc::new(SensorData { reporter: reporter.to_string(), values, received })
+    }
+
+    #[test]
+    fn grid_adapts_to_host_count() {
+        assert_eq!(grid(1, (1024, 600)), (1, 1));
+        assert_eq!(grid(2, (1024, 600)), (2, 1));
+        assert_eq!(grid(3, (1024, 600)), (2, 2));
+        assert_eq!(grid(5, (1024, 600)), (3, 2));
+        assert_eq!(grid(3, (520, 1200)), (2, 2));
+        assert_eq!(grid(2, (520, 1200)), (1, 2));
+    }
+
+    #[test]
+    fn cards_fill_the_panel_row_by_row() {
+        assert_eq!(card_rects(3, (1024, 600)), vec![(16, 16, 490, 278), (518, 16, 490, 278), (16, 306, 490, 278)]);
+        assert_eq!(card_rects(1, (520, 1200)), vec![(16, 16, 488, 1168)]);
+    }
+
+    #[test]
+    fn summarizes_hosts_from_their_latest_report() {
+        let clock = ManualClock::new();
+        let old = clock.now();
+        clock.advance(Duration::from_secs(30));
+        let now = clock.now();
+        let mut state = State::init(now);
+        state.sensor_data = vec![
+            report("windows-sensor-agent", vec![("cpu_utilization", "10")], old),
+            report("hue-sensor-agent", vec![("hue_temperature", "21.5")], now),
+            report("linux-sensor-agent", vec![("cpu_utilization", "20"), ("cpu_temp", "55"), ("gpu_junction_temp", "81")], now),
+            report("linux-sensor-agent", vec![("cpu_utilization", "30"), ("gpu_utilization", "95"), ("gpu_junction_temp", "84")], now)
+        ];
+
+        let mut panel = OverviewPanel::new(&toml::from_str("").unwrap());
+        panel.update(&state, Duration::from_millis(16));
+
+        assert_eq!(panel.hosts, vec![
+            HostSummary { name: "linux".to_string(), last_report: now, cpu: Some(30.0), gpu: Some(95.0), hottest: Some(("gpu junction".to_string(), 84.0)), history: vec![20.0, 30.0] },
+            HostSummary { name: "windows".to_string(), last_report: old, cpu: Some(10.0), gpu: None, hottest: None, history: vec![10.0] }
+        ]);
+        assert_eq!(panel.offline_after, Duration::from_secs(10));
+        assert_eq!(panel.drop_after, Duration::from_secs(300));
+
+        // Without a new report the summaries are kept
+        panel.hosts.clear();
+        panel.update(&state, Duration::from_millis(16));
+        assert!(panel.hosts.is_empty());
+    }
+
+    #[test]
+    fn offline_hosts_go_last_and_are_dropped() {
+        let clock = ManualClock::new();
+        let host = |name: &str, last_report: Instant| HostSummary { name: name.to_string(), last_report, cpu: None, gpu: None, hottest: None, history: Vec::new() };
+        let active = |hosts: &[HostSummary], now: Instant| active_hosts(hosts, now, Duration::from_secs(10), Duration::from_secs(60)).iter()
+            .map(|(host, online)| (host.name.clone(), *online))
+            .collect::<Vec<(String, bool)>>();
+
+        let first = clock.now();
+        clock.advance(Duration::from_secs(20));
+        let hosts = vec![host("alpha", first), host("beta", clock.now()), host("gamma", clock.now())];
+
+        assert_eq!(active(&hosts, clock.now()), vec![("beta".to_string(), true), ("gamma".to_string(), true), ("alpha".to_string(), false)]);
+
+        clock.advance(Duration::from_secs(50));
+        assert_eq!(active(&hosts, clock.now()), vec![("beta".to_string(), false), ("gamma".to_string(), false)]);
+    }
+}
@@ -1,9 +1,224 @@
 use std::collections::HashMap;
-use raylib::core::text::Font;
-use raylib::core::texture::Texture2D;
-use raylib::core::drawing::RaylibDrawHandle;
+use raylib::core::drawing::RaylibDraw;
 use crate::data::SensorData;
+use crate::state::State;
+use crate::config::{Config, PanelRule};
+use crate::presence;
+use crate::viewport::Orientation;
+use crate::layout::Layout;
+use crate::layout_panel::LayoutPanel;
+use crate::pending_panel::PendingPanel;
+use crate::overview_panel::OverviewPanel;
+use crate::render::RenderCtx;
+use std::time::{Instant, Duration};
+use std::sync::Arc;
+
+pub const PANEL_NAMES: [&str; 4] = ["windows", "linux", "overview", "pending"];
 
 pub trait Panel {
-    fn draw(fonts: &HashMap<String, Font>, textures: &HashMap<String, Texture2D>, d: &mut RaylibDrawHandle, data: &Vec<SensorData>);
-}
\ No newline at end of file
+    // Called each frame before draw with the latest state and the time since the previous frame.
+    fn update(&mut self, _snapshot: &State, _dt: Duration) {}
+
+    fn draw<D: RaylibDraw>(&mut self, ctx: &mut RenderCtx<D>);
+
+    // Layout drawn for a window of the given orientation. Panels without a portrait variant keep
+    // the landscape layout, letterboxed in portrait windows.
+    fn layout(&self, _orientation: Orientation) -> Orientation {
+        Orientation::LANDSCAPE
+    }
+}
+
+pub enum PanelKind {
+    Layout(LayoutPanel),
+    Overview(OverviewPanel),
+    Pending(PendingPanel)
+}
+
+impl Panel for PanelKind {
+    fn update(&mut self, snapshot: &State, dt: Duration) {
+        match self {
+            PanelKind::Layout(panel) => panel.update(snapshot, dt),
+            PanelKind::Overview(panel) => panel.update(snapshot, dt),
+            PanelKind::Pending(panel) => panel.update(snapshot, dt)
+        }
+    }
+
+    fn draw<D: RaylibDraw>(&mut self, ctx: &mut RenderCtx<D>) {
+        match self {
+            PanelKind::Layout(panel) => panel.draw(ctx),
+            PanelKind::Overview(panel) => panel.draw(ctx),
+            PanelKind::Pending(panel) => panel.draw(ctx)
+        }
+    }
+
+    fn layout(&self, orientation: Orientation) -> Orientation {
+        match self {
+            PanelKind::Layout(panel) => panel.layout(orientation),
+            PanelKind::Overview(panel) => panel.layout(orientation),
+            PanelKind::Pending(panel) => panel.layout(orientation)
+        }
+    }
+}
+
+// Every panel by name, kept across frames so panels can hold state. Panels with a layout file are
+// drawn from it, the overview has its own panel and anything else shows the pending panel. Built
+// again when the config is reloaded.
+pub struct Panels {
+    config: Arc<Config>,
+    panels: HashMap<String, PanelKind>
+}
+
+impl Panels {
+    pub fn build(config: &Arc<Config>, layouts: &HashMap<String, Layout>) -> Panels {
+        let panels = panel_names(layouts).into_iter().map(|name| {
+            let panel = match (layouts.get(&name), name.as_str()) {
+                (Some(layout), _) => PanelKind::Layout(LayoutPanel::new(layout.clone())),
+                (None, "overview") => PanelKind::Overview(OverviewPanel::new(config)),
+                (None, _) => PanelKind::Pending(PendingPanel())
+            };
+            (name, panel)
+        }).collect();
+
+        Panels { config: config.clone(), panels }
+    }
+
+    pub fn is_built_from(&self, config: &Arc<Config>) -> bool {
+        Arc::ptr_eq(&self.config, config)
+    }
+
+    pub fn get(&mut self, name: &str) -> &mut PanelKind {
+        let name = if self.panels.contains_key(name) { name } else { "pending" };
+        self.panels.get_mut(name).expect("Missing pending panel")
+    }
+}
+
+// Built-in panels and the loaded layouts, sorted so next_panel cycles through them in a stable order.
+pub fn panel_names(layouts: &HashMap<String, Layout>) -> Vec<String> {
+    let mut names: Vec<String> = PANEL_NAMES.iter().map(|name| name.to_string()).chain(layouts.keys().cloned()).collect();
+    names.sort();
+    names.dedup();
+
+    return names;
+}
+
+// Panel of the first rule whose reporter sent a report within the staleness window and matches the
+// rule's sensor condition, or the fallback panel when no rule matches.
+pub fn rule_panel(config: &Config, data: &[Arc<SensorData>], now: Instant) -> String {
+    let selection = &config.panel_selection;
+
+    config.panel_rules.iter()
+        .find(|rule| rule_matches(rule, data, selection.stale_secs, now))
+        .map_or(selection.fallback.clone(), |rule| rule.panel.clone())
+}
+
+fn rule_matches(rule: &PanelRule, data: &[Arc<SensorData>], stale_secs: u64, now: Instant) -> bool {
+    let stale = Duration::from_secs(rule.stale_secs.unwrap_or(stale_secs));
+    let latest = data.iter().rev()
+        .find(|d| { d.reporter == rule.reporter })
+        .filter(|d| { now.saturating_duration_since(d.received) < stale });
+
+    match (latest, &rule.key) {
+        (None, _) => false,
+        (Some(_), None) => true,
+        (Some(latest), Some(key)) => latest.values.get(key)
+            .is_some_and(|value| presence::value_matches(rule.equals.as_deref(), rule.above, rule.below, value))
+    }
+}
+
+// A panel shown from an action wins over the carousel, which wins over the rules.
+pub fn select_panel(state: &State) -> String {
+    state.panel_override.clone()
+        .or_else(|| state.carousel.as_ref().map(|carousel| carousel.panel.clone()))
+        .unwrap_or_else(|| state.active_panel.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+    use crate::state::StateExt;
+    use std::collections::HashMap;
+
+    fn with_report(mut state: State, reporter: &str, received: Instant) -> State {
+        state.sensor_data.push(Arc::new(SensorData { reporter: reporter.to_string(), values: HashMap::new(), received }));
+        state
+    }
+
+    fn with_values(mut state: State, reporter: &str, values: Vec<(&str, &str)>, received: Instant) -> State {
+        let values = values.into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        state.sensor_data.push(Arc::new(SensorData { reporter: reporter.to_string(), values, received }));
+        state
+    }
+
+    fn config(source: &str) -> Config {
+        toml::from_str(source).unwrap()
+    }
+
+    #[test]
+    fn selects_pending_without_data() {
+        let clock = ManualClock::new();
+
+        assert_eq!(rule_panel(&config(""), &State::init(clock.now()).sensor_data, clock.now()), "pending");
+    }
+
+    #[test]
+    fn windows_wins_over_linux() {
+        let clock = ManualClock::new();
+        let state = with_report(with_report(State::init(clock.now()), "linux-sensor-agent", clock.now()), "windows-sensor-agent", clock.now());
+
+        assert_eq!(rule_panel(&config(""), &state.sensor_data, clock.now()), "windows");
+    }
+
+    #[test]
+    fn switches_away_from_stale_data() {
+        let clock = ManualClock::new();
+        let state = with_report(State::init(clock.now()), "windows-sensor-agent", clock.now());
+        clock.advance(Duration::from_secs(5));
+        let state = with_report(state, "linux-sensor-agent", clock.now());
+
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(rule_panel(&config(""), &state.sensor_data, clock.now()), "linux");
+
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(rule_panel(&config(""), &state.sensor_data, clock.now()), "pending");
+    }
+
+    #[test]
+    fn override_wins_over_data() {
+        let clock = ManualClock::new();
+        let mut state = with_report(State::init(clock.now()), "windows-sensor-agent", clock.now());
+        state.active_panel = "windows".to_string();
+        state.panel_override = Some("linux".to_string());
+
+        assert_eq!(select_panel(&state), "linux");
+    }
+
+    #[test]
+    fn carousel_wins_over_rules() {
+        let clock = ManualClock::new();
+        let config = config("[[carousel_pages]]\npanel = 'linux'");
+        let mut state = State::init(clock.now()).apply_carousel(&config.carousel_pages, &config.carousel, clock.now());
+        state.active_panel = "windows".to_string();
+        assert_eq!(select_panel(&state), "linux");
+
+        state.panel_override = Some("pending".to_string());
+        assert_eq!(select_panel(&state), "pending");
+    }
+
+    #[test]
+    fn rules_match_in_order_on_sensor_values() {
+        let clock = ManualClock::new();
+        let config = config("[panel_selection]\nstale_secs = 30\nfallback = 'pending'\n\n\
+                             [[panel_rules]]\npanel = 'overview'\nreporter = 'windows-sensor-agent'\nkey = 'gpu_utilization'\nabove = 50\n\n\
+                             [[panel_rules]]\npanel = 'windows'\nreporter = 'windows-sensor-agent'\nstale_secs = 5");
+        let busy = with_values(State::init(clock.now()), "windows-sensor-agent", vec![("gpu_utilization", "90")], clock.now());
+        let quiet = with_values(State::init(clock.now()), "windows-sensor-agent", vec![("gpu_utilization", "10")], clock.now());
+
+        assert_eq!(rule_panel(&config, &busy.sensor_data, clock.now()), "overview");
+        assert_eq!(rule_panel(&config, &quiet.sensor_data, clock.now()), "windows");
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(rule_panel(&config, &busy.sensor_data, clock.now()), "overview");
+        assert_eq!(rule_panel(&config, &quiet.sensor_data, clock.now()), "pending");
+    }
+}
@@ -1,42 +1,29 @@
-use raylib::core::texture::Texture2D;
-use raylib::core::drawing::{RaylibDrawHandle, RaylibDraw};
+use raylib::core::drawing::RaylibDraw;
 use raylib::color::Color;
-use raylib::core::text::Font;
-use std::collections::HashMap;
-use crate::textures::get_texture;
-use crate::data::SensorData;
-use crate::fonts::get_font;
-use raylib::prelude::Vector2;
-use chrono::Local;
 use crate::panel::Panel;
+use crate::render::RenderCtx;
 
 pub(crate) struct PendingPanel();
 
 impl Panel for PendingPanel {
-    fn draw(fonts: &HashMap<String, Font>, textures: &HashMap<String, Texture2D>, d: &mut RaylibDrawHandle, data: &Vec<SensorData>) {
-        let background = get_texture(textures, "pending_background");
+    fn draw<D: RaylibDraw>(&mut self, ctx: &mut RenderCtx<D>) {
+        let background = ctx.texture("pending_background");
 
-        d.draw_texture(&background, 0, 0, Color::WHITE);
-        d.clear_background(Color::WHITE);
-
-        let hue_data = data.iter()
-            .filter(|d| { d.reporter == "hue-sensor-agent" })
-            .collect::<Vec<&SensorData>>();
-        let latest_data = hue_data.last();
-
-        if latest_data.is_some() {
-            let office_temp: f32 = latest_data.unwrap().values.get("hue_temperature").unwrap_or(&"0".to_string()).parse().unwrap();
+        ctx.d.draw_texture(background, 0, 0, Color::WHITE);
+        ctx.d.clear_background(Color::WHITE);
 
+        if ctx.sensors.has_data("hue-sensor-agent") {
+            let office_temp = ctx.sensors.number("hue-sensor-agent", "hue_temperature").unwrap_or(0.0);
             let temp = format!("{:.1}   C", office_temp);
-            d.draw_text_ex(get_font(fonts, "calibri_40_bold"), &temp, Vector2::new(457.0, 61.0), 40.0, 0.0, Color::WHITE);
-            d.draw_circle(546, 71, 6.0, Color::WHITE);
-            d.draw_circle(546, 71, 3.0, Color::BLACK);
+            ctx.text("calibri_40_bold", &temp, (457, 61), Color::WHITE);
+            ctx.d.draw_circle(546, 71, 6.0, Color::WHITE);
+            ctx.d.draw_circle(546, 71, 3.0, Color::BLACK);
         }
 
-        let date = Local::now().format("%H:%M:%S").to_string();
-        d.draw_rectangle(570, 278, 153, 44, Color::BLACK);
-        d.draw_text_ex(get_font(fonts, "calibri_40_bold"), &date, Vector2::new(575.0, 282.0), 40.0, 0.0, Color::WHITE);
+        let date = ctx.clock.local_now().format("%H:%M:%S").to_string();
+        ctx.d.draw_rectangle(570, 278, 153, 44, Color::BLACK);
+        ctx.text("calibri_40_bold", &date, (575, 282), Color::WHITE);
 
-        d.draw_text_ex(get_font(fonts, "calibri_30"), "SENSORPANEL", Vector2::new(427.0, 465.0), 30.0, 0.0, Color::WHITE);
+        ctx.text("calibri_30", "SENSORPANEL", (427, 465), Color::WHITE);
     }
-}
\ No newline at end of file
+}
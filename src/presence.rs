@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::time::{Instant, Duration};
+use crate::config::{Config, PresenceSource, PresenceMode};
+
+#[derive(Clone, Debug)]
+pub struct SourceReading {
+    pub present: bool,
+    pub received: Instant
+}
+
+pub fn source_id(source: &PresenceSource) -> String {
+    format!("{}/{}", source.reporter.as_deref().unwrap_or("*"), source.key)
+}
+
+pub fn read_sources(config: &Config, reporter: &str, values: &HashMap<String, String>, received: Instant) -> Vec<(String, SourceReading)> {
+    config.presence_sources.iter()
+        .filter(|source| source.reporter.as_ref().is_none_or(|r| r == reporter))
+        .filter_map(|source| values.get(&source.key).map(|value| (source, value)))
+        .map(|(source, value)| (source_id(source), SourceReading { present: is_truthy(source, value), received }))
+        .collect()
+}
+
+pub fn is_present(config: &Config, readings: &HashMap<String, SourceReading>, now: Instant) -> bool {
+    let mut sources = config.presence_sources.iter().map(|source| {
+        readings.get(&source_id(source))
+            .filter(|reading| source.max_age_secs.is_none_or(|max_age| now.saturating_duration_since(reading.received) <= Duration::from_secs(max_age)))
+            .is_some_and(|reading| reading.present)
+    });
+
+    match config.presence_mode {
+        PresenceMode::ANY => sources.any(|present| present),
+        PresenceMode::ALL => sources.all(|present| present)
+    }
+}
+
+fn is_truthy(source: &PresenceSource, value: &str) -> bool {
+    value_matches(source.equals.as_deref(), source.above, source.below, value)
+}
+
+// Compares against equals, or above/below for numbers, otherwise checks for a true-ish value.
+pub fn value_matches(equals: Option<&str>, above: Option<f32>, below: Option<f32>, value: &str) -> bool {
+    let value = value.trim();
+
+    if let Some(expected) = equals {
+        return value == expected;
+    }
+
+    if above.is_some() || below.is_some() {
+        return match value.parse::<f32>() {
+            Ok(number) => above.is_none_or(|above| number > above) && below.is_none_or(|below| number < below),
+            Err(_) => false
+        };
+    }
+
+    matches!(value.to_lowercase().as_str(), "true" | "1" | "yes" | "on")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(reporter: Option<&str>, key: &str) -> PresenceSource {
+        PresenceSource {
+            reporter: reporter.map(|r| r.to_string()),
+            key: key.to_string(),
+            equals: None,
+            above: None,
+            below: None,
+            max_age_secs: None
+        }
+    }
+
+    fn config(sources: Vec<PresenceSource>, mode: PresenceMode) -> Config {
+        let mut config: Config = toml::from_str("").unwrap();
+        config.presence_sources = sources;
+        config.presence_mode = mode;
+        config
+    }
+
+    fn values(pairs: Vec<(&str, &str)>) -> HashMap<String, String> {
+        pairs.into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn default_source_reads_hue_presence() {
+        let config: Config = toml::from_str("").unwrap();
+        let readings = read_sources(&config, "hue-sensor-agent", &values(vec![("hue_presence", "true")]), Instant::now());
+
+        assert_eq!(readings.len(), 1);
+        assert!(readings[0].1.present);
+    }
+
+    #[test]
+    fn truthiness_rules() {
+        let mut threshold = source(None, "cpu_utilization");
+        threshold.above = Some(5.0);
+        assert!(is_truthy(&threshold, "12.5"));
+        assert!(!is_truthy(&threshold, "2"));
+        assert!(!is_truthy(&threshold, "n/a"));
+
+        let mut equals = source(None, "state");
+        equals.equals = Some("occupied".to_string());
+        assert!(is_truthy(&equals, "occupied"));
+        assert!(!is_truthy(&equals, "true"));
+
+        let plain = source(None, "motion");
+        assert!(is_truthy(&plain, "Yes"));
+        assert!(!is_truthy(&plain, "false"));
+    }
+
+    #[test]
+    fn ignores_sources_for_other_reporters() {
+        let config = config(vec![source(Some("office-sensor"), "motion")], PresenceMode::ANY);
+
+        assert!(read_sources(&config, "kitchen-sensor", &values(vec![("motion", "true")]), Instant::now()).is_empty());
+    }
+
+    #[test]
+    fn combines_sources_with_any_and_all() {
+        let sources = vec![source(Some("office"), "motion"), source(Some("kitchen"), "motion")];
+        let now = Instant::now();
+        let mut readings = HashMap::new();
+        readings.insert("office/motion".to_string(), SourceReading { present: true, received: now });
+        readings.insert("kitchen/motion".to_string(), SourceReading { present: false, received: now });
+
+        assert!(is_present(&config(sources.clone(), PresenceMode::ANY), &readings, now));
+        assert!(!is_present(&config(sources, PresenceMode::ALL), &readings, now));
+    }
+
+    #[test]
+    fn stale_readings_count_as_absent() {
+        let mut pc_active = source(Some("windows-sensor-agent"), "cpu_utilization");
+        pc_active.max_age_secs = Some(30);
+        let received = Instant::now();
+        let mut readings = HashMap::new();
+        readings.insert(source_id(&pc_active), SourceReading { present: true, received });
+        let config = config(vec![pc_active], PresenceMode::ANY);
+
+        assert!(is_present(&config, &readings, received + Duration::from_secs(10)));
+        assert!(!is_present(&config, &readings, received + Duration::from_secs(31)));
+    }
+}
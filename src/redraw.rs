@@ -0,0 +1,103 @@
+use std::sync::mpsc::{self, Sender, Receiver};
+use std::time::{Instant, Duration};
+use chrono::{NaiveDateTime, Timelike};
+
+pub struct Redraw {
+    sender: Sender<()>,
+    receiver: Receiver<()>,
+    animating_until: Option<Instant>
+}
+
+impl Redraw {
+    pub fn new() -> Redraw {
+        let (sender, receiver) = mpsc::channel();
+
+        Redraw {
+            sender,
+            receiver,
+            animating_until: None
+        }
+    }
+
+    pub fn trigger(&self) -> Sender<()> {
+        self.sender.clone()
+    }
+
+    pub fn animate_for(&mut self, duration: Duration, now: Instant) {
+        let until = now + duration;
+        if self.animating_until.is_none_or(|current| current < until) {
+            self.animating_until = Some(until);
+        }
+    }
+
+    pub fn is_animating(&self, now: Instant) -> bool {
+        self.animating_until.is_some_and(|until| until > now)
+    }
+
+    pub fn wait(&mut self, now: Instant, local_now: NaiveDateTime) {
+        if self.is_animating(now) {
+            return;
+        }
+        self.animating_until = None;
+
+        let _ = self.receiver.recv_timeout(until_next_second(local_now));
+        while self.receiver.try_recv().is_ok() {}
+    }
+}
+
+fn until_next_second(local_now: NaiveDateTime) -> Duration {
+    let into_second = Duration::from_nanos((local_now.nanosecond() % 1_000_000_000) as u64);
+
+    Duration::from_secs(1) - into_second
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn local(millis: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd(2021, 6, 7).and_hms_milli(12, 0, 0, millis)
+    }
+
+    #[test]
+    fn waits_until_next_clock_second() {
+        assert_eq!(until_next_second(local(0)), Duration::from_secs(1));
+        assert_eq!(until_next_second(local(750)), Duration::from_millis(250));
+    }
+
+    #[test]
+    fn trigger_wakes_waiting_renderer() {
+        let mut redraw = Redraw::new();
+        redraw.trigger().send(()).unwrap();
+        redraw.trigger().send(()).unwrap();
+
+        let start = Instant::now();
+        redraw.wait(start, local(0));
+
+        assert!(start.elapsed() < Duration::from_millis(500));
+        assert!(redraw.receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn animation_keeps_renderer_busy_until_it_ends() {
+        let mut redraw = Redraw::new();
+        let now = Instant::now();
+        redraw.animate_for(Duration::from_secs(2), now);
+        redraw.animate_for(Duration::from_secs(1), now);
+
+        assert!(redraw.is_animating(now + Duration::from_millis(1500)));
+        assert!(!redraw.is_animating(now + Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn animation_does_not_wait_for_next_second() {
+        let mut redraw = Redraw::new();
+        let start = Instant::now();
+        redraw.animate_for(Duration::from_secs(1), start);
+
+        redraw.wait(start, local(0));
+
+        assert!(start.elapsed() < Duration::from_millis(500));
+    }
+}
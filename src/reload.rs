@@ -0,0 +1,136 @@
+use crate::config::{Config, parse_config};
+use crate::overrides::Override;
+use crate::log::{Log, LogExt, LogLevel};
+use crate::websocket::SensorReport;
+use arc_swap::ArcSwap;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+const WATCH_INTERVAL_MILLIS: u64 = 500;
+
+static HANGUP_RECEIVED: AtomicBool = AtomicBool::new(false);
+
+pub struct ConfigStore {
+    path: String,
+    overrides: Vec<Override>,
+    current: ArcSwap<Config>
+}
+
+impl ConfigStore {
+    pub fn new(path: &str, overrides: &[Override], config: Config) -> ConfigStore {
+        ConfigStore {
+            path: path.to_string(),
+            overrides: overrides.to_vec(),
+            current: ArcSwap::from_pointee(config)
+        }
+    }
+
+    pub fn load(&self) -> Arc<Config> {
+        self.current.load_full()
+    }
+
+    // Reads the config file again and swaps it in, returning the previous config.
+    // An unreadable or invalid file leaves the current config in place.
+    pub fn reload(&self) -> Result<Arc<Config>, String> {
+        let config_str = fs::read_to_string(&self.path)
+            .map_err(|error| format!("Could not read {}: {}", self.path, error))?;
+        let config = parse_config(&config_str, &self.overrides)?;
+
+        Ok(self.current.swap(Arc::new(config)))
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        fs::metadata(&self.path).and_then(|m| m.modified()).ok()
+    }
+}
+
+// Requests a reload through the reload_config action when the config file changes or on SIGHUP.
+pub fn watch_setup(store: Arc<ConfigStore>, reload_tx: Sender<SensorReport>) {
+    let registered = unsafe {
+        signal_hook_registry::register(libc::SIGHUP, || HANGUP_RECEIVED.store(true, Ordering::SeqCst))
+    };
+    if let Err(error) = registered {
+        Log::log(LogLevel::ERROR, &*format!("Failed to register SIGHUP handler: {}", error));
+    }
+
+    thread::spawn(move || {
+        let mut last_modified = store.modified();
+
+        loop {
+            thread::sleep(Duration::from_millis(WATCH_INTERVAL_MILLIS));
+
+            let modified = store.modified();
+            let changed = modified != last_modified;
+            last_modified = modified;
+
+            if !(changed || HANGUP_RECEIVED.swap(false, Ordering::SeqCst)) {
+                continue;
+            }
+
+            Log::log(LogLevel::INFO, &*format!("Reloading {}", store.path));
+            if reload_tx.send(reload_request()).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+fn reload_request() -> SensorReport {
+    let mut sensors = HashMap::new();
+    sensors.insert("reload_config".to_string(), String::new());
+
+    SensorReport {
+        reporter: "sensorpanel".to_string(),
+        topic: "actions".to_string(),
+        sensors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::path::PathBuf;
+    use std::process;
+
+    fn config_file(name: &str, content: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("sensorpanel-{}-{}.toml", name, process::id()));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn reload_swaps_in_valid_config() {
+        let path = config_file("reload-valid", "relay_host = 'old-relay'\nfps = 5");
+        let store = ConfigStore::new(&path.display().to_string(), &[], parse_config(&fs::read_to_string(&path).unwrap(), &[]).unwrap());
+
+        fs::write(&path, "relay_host = 'new-relay'\nfps = 10").unwrap();
+        let previous = store.reload().unwrap();
+
+        assert_eq!(previous.relay_host, "old-relay");
+        assert_eq!(store.load().relay_host, "new-relay");
+        assert_eq!(store.load().fps, 10);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reload_keeps_previous_config_on_invalid_edit() {
+        let path = config_file("reload-invalid", "relay_host = 'relay'");
+        let store = ConfigStore::new(&path.display().to_string(), &[], parse_config(&fs::read_to_string(&path).unwrap(), &[]).unwrap());
+
+        fs::write(&path, "relay_host = ").unwrap();
+        assert!(store.reload().is_err());
+
+        fs::write(&path, "fps = 0").unwrap();
+        assert!(store.reload().is_err());
+
+        fs::remove_file(&path).unwrap();
+        assert!(store.reload().is_err());
+        assert_eq!(store.load().relay_host, "relay");
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+use raylib::color::Color;
+use raylib::core::drawing::RaylibDraw;
+use raylib::core::text::Font;
+use raylib::core::texture::Texture2D;
+use raylib::prelude::Vector2;
+use crate::clock::Clock;
+use crate::fonts::{get_font, font_size};
+use crate::sensors::Sensors;
+use crate::textures::get_texture;
+use crate::viewport::Orientation;
+
+// Colours shared by panels that aren't drawn from textures.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub background: Color,
+    pub card: Color,
+    pub border: Color,
+    pub text: Color,
+    pub muted: Color,
+    pub accent: Color
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            background: Color::WHITE,
+            card: Color::new(20, 20, 20, 255),
+            border: Color::DARKGRAY,
+            text: Color::WHITE,
+            muted: Color::GRAY,
+            accent: Color::SKYBLUE
+        }
+    }
+}
+
+// Everything a panel draws a frame with: the draw handle, loaded resources, the theme, the clock
+// and the report history indexed by reporter.
+pub struct RenderCtx<'a, D: RaylibDraw> {
+    pub d: &'a mut D,
+    pub fonts: &'a HashMap<String, Font>,
+    pub textures: &'a HashMap<String, Texture2D>,
+    pub theme: &'a Theme,
+    pub clock: &'a dyn Clock,
+    pub sensors: &'a Sensors<'a>,
+    pub layout: Orientation
+}
+
+impl<'a, D: RaylibDraw> RenderCtx<'a, D> {
+    pub fn texture(&self, name: &str) -> &'a Texture2D {
+        get_texture(self.textures, name)
+    }
+
+    // Draws text with a font at the size it was loaded at.
+    pub fn text(&mut self, font: &str, text: &str, (x, y): (i32, i32), color: Color) {
+        self.d.draw_text_ex(get_font(self.fonts, font), text, Vector2::new(x as f32, y as f32), font_size(font) as f32, 0.0, color);
+    }
+}
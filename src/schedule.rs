@@ -0,0 +1,140 @@
+use chrono::{NaiveDateTime, NaiveTime, Datelike, Weekday, Timelike};
+use crate::config::{Config, Schedule};
+
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+pub fn active_schedule(config: &Config, now: NaiveDateTime) -> Option<&Schedule> {
+    config.schedules.iter().find(|schedule| is_active(schedule, now))
+}
+
+pub fn presence_threshold_secs(config: &Config, now: NaiveDateTime) -> u32 {
+    active_schedule(config, now)
+        .and_then(|schedule| schedule.presence_threshold_secs)
+        .unwrap_or(config.presence_threshold_secs)
+}
+
+pub fn is_active(schedule: &Schedule, now: NaiveDateTime) -> bool {
+    let (start, end) = match (parse_time(&schedule.start), parse_time(&schedule.end)) {
+        (Some(start), Some(end)) => (start, end),
+        _ => return false
+    };
+    let time = now.time().with_second(0).unwrap_or(now.time());
+    let today = now.weekday();
+
+    if start <= end {
+        start <= time && time < end && matches_day(&schedule.days, today)
+    } else {
+        (time >= start && matches_day(&schedule.days, today)) || (time < end && matches_day(&schedule.days, today.pred()))
+    }
+}
+
+pub fn parse_time(time: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M").ok()
+}
+
+fn matches_day(days: &Option<String>, day: Weekday) -> bool {
+    let days = match days {
+        Some(days) => days.trim().to_lowercase(),
+        None => return true
+    };
+    let day_index = day.num_days_from_monday() as usize;
+
+    days.split(',').map(|item| item.trim()).any(|item| {
+        if item == "*" {
+            return true;
+        }
+
+        match item.split_once('-') {
+            Some((first, last)) => match (weekday_index(first), weekday_index(last)) {
+                (Some(first), Some(last)) if first <= last => first <= day_index && day_index <= last,
+                (Some(first), Some(last)) => day_index >= first || day_index <= last,
+                _ => false
+            },
+            None => weekday_index(item) == Some(day_index)
+        }
+    })
+}
+
+pub fn is_valid_days(days: &str) -> bool {
+    days.trim().to_lowercase().split(',').map(|item| item.trim()).all(|item| {
+        match item.split_once('-') {
+            _ if item == "*" => true,
+            Some((first, last)) => weekday_index(first).is_some() && weekday_index(last).is_some(),
+            None => weekday_index(item).is_some()
+        }
+    })
+}
+
+fn weekday_index(day: &str) -> Option<usize> {
+    let prefix = day.trim().get(0..3)?;
+    WEEKDAYS.iter().position(|d| *d == prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use crate::config::ScheduleMode;
+
+    fn schedule(days: Option<&str>, start: &str, end: &str) -> Schedule {
+        Schedule {
+            days: days.map(|d| d.to_string()),
+            start: start.to_string(),
+            end: end.to_string(),
+            mode: ScheduleMode::OFF,
+            presence_threshold_secs: None
+        }
+    }
+
+    // 2021-06-07 is a Monday
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd(2021, 6, day).and_hms(hour, minute, 0)
+    }
+
+    #[test]
+    fn matches_window_within_a_day() {
+        let work_hours = schedule(Some("mon-fri"), "08:00", "17:00");
+
+        assert!(is_active(&work_hours, at(7, 8, 0)));
+        assert!(is_active(&work_hours, at(11, 16, 59)));
+        assert!(!is_active(&work_hours, at(7, 17, 0)));
+        assert!(!is_active(&work_hours, at(12, 12, 0)));
+    }
+
+    #[test]
+    fn matches_window_across_midnight() {
+        let nights = schedule(Some("fri"), "22:00", "07:00");
+
+        assert!(is_active(&nights, at(11, 23, 0)));
+        assert!(is_active(&nights, at(12, 6, 59)));
+        assert!(!is_active(&nights, at(11, 6, 0)));
+        assert!(!is_active(&nights, at(12, 22, 30)));
+    }
+
+    #[test]
+    fn matches_day_lists_and_wrapping_ranges() {
+        assert!(matches_day(&Some("sat,sun".to_string()), Weekday::Sun));
+        assert!(!matches_day(&Some("sat,sun".to_string()), Weekday::Mon));
+        assert!(matches_day(&Some("fri-mon".to_string()), Weekday::Sat));
+        assert!(!matches_day(&Some("fri-mon".to_string()), Weekday::Wed));
+        assert!(matches_day(&Some("*".to_string()), Weekday::Wed));
+        assert!(matches_day(&None, Weekday::Wed));
+    }
+
+    #[test]
+    fn invalid_times_never_match() {
+        assert!(!is_active(&schedule(None, "25:00", "07:00"), at(7, 1, 0)));
+    }
+
+    #[test]
+    fn threshold_falls_back_to_config() {
+        let mut config: Config = toml::from_str("presence_threshold_secs = 600").unwrap();
+        let mut work_hours = schedule(None, "08:00", "17:00");
+        work_hours.mode = ScheduleMode::THRESHOLD;
+        work_hours.presence_threshold_secs = Some(1800);
+        config.schedules.push(work_hours);
+
+        assert_eq!(presence_threshold_secs(&config, at(7, 9, 0)), 1800);
+        assert_eq!(presence_threshold_secs(&config, at(7, 18, 0)), 600);
+    }
+}
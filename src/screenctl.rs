@@ -1,79 +1,784 @@
-use std::process::Command;
+use std::process::{Command, Stdio};
 use std::path::Path;
 use std::thread;
+use std::fs;
+use std::io::Write;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
+use std::time::Duration;
 use crate::log::{Log, LogExt, LogLevel};
+use crate::config::{Config, ScreenBackend, ScreenControlConfig, UsbPowerConfig};
+use crate::websocket::SensorReport;
 
-pub trait ScreenControl {
-    fn turn_on(&self) -> bool;
-    fn turn_off(&self) -> bool;
+// turn_on and turn_off return the power state queried to verify the switch, None when not verifying.
+pub trait ScreenControl: Send {
+    fn turn_on(&self) -> Result<Option<bool>, String>;
+    fn turn_off(&self) -> Result<Option<bool>, String>;
     fn should_clear_screen(&self) -> bool;
+    fn query_power(&self) -> Option<bool> { None }
+    fn set_brightness(&self, _level: u8) -> bool { false }
+    fn has_hardware_brightness(&self) -> bool { false }
 }
 
 const TVSERVICE_PATH: &str = "/usr/bin/tvservice";
-const UHUBCTL_PATH: &str = "/home/pi/devel/uhubctl/uhubctl";
-
-struct TvServiceScreenControl {}
-
-impl ScreenControl for TvServiceScreenControl {
-    fn turn_on(&self) -> bool {
-        thread::spawn(|| {
-            Command::new(TVSERVICE_PATH)
-                .arg("-p")
-                .status()
-                .expect("Failed to turn on screen with tvservice");
-
-            if Path::new(UHUBCTL_PATH).exists() {
-                Command::new(UHUBCTL_PATH)
-                    .args(&["-l", "1-1", "-a", "1", "-r", "100"])
-                    .status()
-                    .expect("Failed to turn on screen with uhubctl");
-            }
-        });
+const VCGENCMD_PATH: &str = "/usr/bin/vcgencmd";
+const XSET_PATH: &str = "/usr/bin/xset";
+const CEC_CLIENT_PATH: &str = "/usr/bin/cec-client";
+const DEFAULT_BACKLIGHT: &str = "/sys/class/backlight/rpi_backlight";
+const BACKLIGHT_CLASS: &str = "/sys/class/backlight";
+
+static SWITCH_LOCK: Mutex<()> = Mutex::new(());
+static SWITCH_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Debug, PartialEq)]
+struct ScreenCommand {
+    program: String,
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+    stdin: Option<String>
+}
 
-        return true;
+impl ScreenCommand {
+    fn new(program: &str, args: &[&str]) -> ScreenCommand {
+        ScreenCommand {
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            env: Vec::new(),
+            stdin: None
+        }
     }
 
-    fn turn_off(&self) -> bool {
-        thread::spawn(|| {
-            Command::new(TVSERVICE_PATH)
-                .arg("-o")
-                .status()
-                .expect("Failed to turn on screen with tvservice");
+    fn from_vec(command: &[String]) -> Option<ScreenCommand> {
+        let (program, args) = command.split_first()?;
 
-            if Path::new(UHUBCTL_PATH).exists() {
-                Command::new(UHUBCTL_PATH)
-                    .args(&["-l", "1-1", "-a", "0", "-r", "100"])
-                    .status()
-                    .expect("Failed to turn on screen with uhubctl");
+        Some(ScreenCommand {
+            program: program.clone(),
+            args: args.to_vec(),
+            env: Vec::new(),
+            stdin: None
+        })
+    }
+}
+
+// Power is on when the status output contains the pattern, or off when on_match is false.
+#[derive(Clone, Debug, PartialEq)]
+struct StatusQuery {
+    command: ScreenCommand,
+    pattern: String,
+    on_match: bool
+}
+
+impl StatusQuery {
+    fn new(command: ScreenCommand, pattern: &str, on_match: bool) -> StatusQuery {
+        StatusQuery { command, pattern: pattern.to_string(), on_match }
+    }
+
+    fn run(&self) -> Option<bool> {
+        let output = run_command(&self.command).ok()?;
+        Some(output.contains(&self.pattern) == self.on_match)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct RetryPolicy {
+    retries: u32,
+    delay: Duration,
+    verify: bool
+}
+
+impl RetryPolicy {
+    fn from_config(screen_config: &ScreenControlConfig) -> RetryPolicy {
+        RetryPolicy {
+            retries: screen_config.retries,
+            delay: Duration::from_millis(screen_config.retry_delay_ms),
+            verify: screen_config.verify
+        }
+    }
+
+    // Runs switch until it succeeds and, when verifying, the screen reports the expected power state.
+    // Returns the power state last queried, query is only called when verifying.
+    fn run(&self, expected: bool, switch: impl Fn() -> Result<(), String>, query: impl Fn() -> Option<bool>) -> Result<Option<bool>, String> {
+        let mut last_error = String::new();
+
+        for attempt in 0..=self.retries {
+            if attempt > 0 {
+                Log::log(LogLevel::INFO, &*format!("Retrying screen power {} ({}/{})", on_off(expected), attempt, self.retries));
+                thread::sleep(self.delay);
+            }
+
+            let result = switch().and_then(|_| {
+                let power = if self.verify { query() } else { None };
+                match power {
+                    Some(power) if power != expected => Err(format!("screen reports power {} after switching {}", on_off(power), on_off(expected))),
+                    _ => Ok(power)
+                }
+            });
+
+            match result {
+                Ok(power) => return Ok(power),
+                Err(error) => {
+                    Log::log(LogLevel::ERROR, &*format!("Failed to switch screen {}: {}", on_off(expected), error));
+                    last_error = error;
+                }
             }
-        });
+        }
+
+        Err(last_error)
+    }
+}
+
+fn on_off(power: bool) -> &'static str {
+    if power { "on" } else { "off" }
+}
+
+struct CommandScreenControl {
+    on: Vec<ScreenCommand>,
+    off: Vec<ScreenCommand>,
+    status: Option<StatusQuery>,
+    retry: RetryPolicy,
+    clear_screen: bool
+}
 
-        return true;
+impl ScreenControl for CommandScreenControl {
+    fn turn_on(&self) -> Result<Option<bool>, String> {
+        self.retry.run(true, || run_commands(&self.on), || self.query_power())
+    }
+
+    fn turn_off(&self) -> Result<Option<bool>, String> {
+        self.retry.run(false, || run_commands(&self.off), || self.query_power())
+    }
+
+    fn should_clear_screen(&self) -> bool { self.clear_screen }
+
+    fn query_power(&self) -> Option<bool> {
+        self.status.as_ref().and_then(|status| status.run())
+    }
+}
+
+fn run_commands(commands: &[ScreenCommand]) -> Result<(), String> {
+    for command in commands.iter() {
+        run_command(command)?;
+    }
+
+    Ok(())
+}
+
+// Runs the command to completion and returns its stdout, or an error including stderr on a non-zero exit.
+fn run_command(command: &ScreenCommand) -> Result<String, String> {
+    let mut child = Command::new(&command.program)
+        .args(&command.args)
+        .envs(command.env.iter().map(|(k, v)| (k, v)))
+        .stdin(if command.stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| format!("failed to run {}: {}", command.program, error))?;
+
+    if let (Some(input), Some(mut stdin)) = (&command.stdin, child.stdin.take()) {
+        stdin.write_all(input.as_bytes())
+            .map_err(|error| format!("failed to write to {}: {}", command.program, error))?;
+    }
+
+    let output = child.wait_with_output()
+        .map_err(|error| format!("failed to wait for {}: {}", command.program, error))?;
+
+    if !output.status.success() {
+        return Err(format!("{} exited with {}: {}", command.program, output.status, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+struct BacklightScreenControl {
+    device: String,
+    retry: RetryPolicy
+}
+
+impl BacklightScreenControl {
+    fn write_bl_power(&self, value: &str) -> Result<(), String> {
+        let path = format!("{}/bl_power", self.device);
+
+        fs::write(&path, value).map_err(|error| format!("failed to write {}: {}", path, error))
+    }
+}
+
+impl ScreenControl for BacklightScreenControl {
+    fn turn_on(&self) -> Result<Option<bool>, String> {
+        self.retry.run(true, || self.write_bl_power("0"), || self.query_power())
+    }
+
+    fn turn_off(&self) -> Result<Option<bool>, String> {
+        self.retry.run(false, || self.write_bl_power("1"), || self.query_power())
     }
 
     fn should_clear_screen(&self) -> bool { false }
+
+    fn query_power(&self) -> Option<bool> {
+        fs::read_to_string(format!("{}/bl_power", self.device)).ok().map(|value| value.trim() == "0")
+    }
+
+    fn set_brightness(&self, level: u8) -> bool { write_brightness(&self.device, level) }
+
+    fn has_hardware_brightness(&self) -> bool { true }
+}
+
+// Adds sysfs backlight brightness to a backend that only switches power.
+struct BacklightBrightness {
+    inner: Box<dyn ScreenControl>,
+    device: String
+}
+
+impl ScreenControl for BacklightBrightness {
+    fn turn_on(&self) -> Result<Option<bool>, String> { self.inner.turn_on() }
+
+    fn turn_off(&self) -> Result<Option<bool>, String> { self.inner.turn_off() }
+
+    fn should_clear_screen(&self) -> bool { self.inner.should_clear_screen() }
+
+    fn query_power(&self) -> Option<bool> { self.inner.query_power() }
+
+    fn set_brightness(&self, level: u8) -> bool { write_brightness(&self.device, level) }
+
+    fn has_hardware_brightness(&self) -> bool { true }
+}
+
+fn write_brightness(device: &str, level: u8) -> bool {
+    let max_path = format!("{}/max_brightness", device);
+    let path = format!("{}/brightness", device);
+
+    let max_brightness = match fs::read_to_string(&max_path).map(|s| s.trim().parse::<u32>()) {
+        Ok(Ok(max)) => max,
+        _ => {
+            Log::log(LogLevel::ERROR, &*format!("Failed to read {}", max_path));
+            return false;
+        }
+    };
+    let value = (max_brightness * level.min(100) as u32 + 50) / 100;
+
+    match fs::write(&path, value.to_string()) {
+        Ok(_) => true,
+        Err(error) => {
+            Log::log(LogLevel::ERROR, &*format!("Failed to write {}: {}", path, error));
+            false
+        }
+    }
+}
+
+fn backlight_device(name: &str) -> Option<String> {
+    if name != "auto" {
+        return Some(name.to_string());
+    }
+
+    let mut devices: Vec<String> = fs::read_dir(BACKLIGHT_CLASS).ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path().display().to_string())
+        .collect();
+    devices.sort();
+
+    devices.into_iter().next()
+}
+
+// Switches USB hub power along with the screen, e.g. for displays powered from the hub.
+// The hub is powered before the screen is turned on and after it is turned off.
+struct UsbPowerControl {
+    inner: Box<dyn ScreenControl>,
+    on: ScreenCommand,
+    off: ScreenCommand,
+    retry: RetryPolicy
+}
+
+impl ScreenControl for UsbPowerControl {
+    fn turn_on(&self) -> Result<Option<bool>, String> {
+        let hub = self.retry.run(true, || run_command(&self.on).map(|_| ()), || None);
+        let screen = self.inner.turn_on();
+        return hub.and(screen);
+    }
+
+    fn turn_off(&self) -> Result<Option<bool>, String> {
+        let screen = self.inner.turn_off();
+        let hub = self.retry.run(false, || run_command(&self.off).map(|_| ()), || None);
+        return screen.and_then(|power| hub.map(|_| power));
+    }
+
+    fn should_clear_screen(&self) -> bool { self.inner.should_clear_screen() }
+
+    fn query_power(&self) -> Option<bool> { self.inner.query_power() }
+
+    fn set_brightness(&self, level: u8) -> bool { self.inner.set_brightness(level) }
+
+    fn has_hardware_brightness(&self) -> bool { self.inner.has_hardware_brightness() }
+}
+
+fn usb_power_commands(usb_config: &UsbPowerConfig) -> Option<(ScreenCommand, ScreenCommand)> {
+    if let (Some(on), Some(off)) = (&usb_config.on_command, &usb_config.off_command) {
+        return Some((ScreenCommand::from_vec(on)?, ScreenCommand::from_vec(off)?));
+    }
+
+    let mut args = Vec::new();
+    if let Some(location) = &usb_config.location {
+        args.extend(vec!["-l".to_string(), location.clone()]);
+    }
+    if let Some(ports) = &usb_config.ports {
+        args.extend(vec!["-p".to_string(), ports.clone()]);
+    }
+    if let Some(retries) = usb_config.retries {
+        args.extend(vec!["-r".to_string(), retries.to_string()]);
+    }
+
+    let with_action = |action: &str| ScreenCommand {
+        program: usb_config.path.clone(),
+        args: args.iter().cloned().chain(vec!["-a".to_string(), action.to_string()]).collect(),
+        env: Vec::new(),
+        stdin: None
+    };
+
+    Some((with_action("1"), with_action("0")))
 }
 
 struct SoftwareScreenControl {}
 
 impl ScreenControl for SoftwareScreenControl {
-    fn turn_on(&self) -> bool {
+    fn turn_on(&self) -> Result<Option<bool>, String> {
         Log::log(LogLevel::INFO, "Turning on screen");
-        return true;
+        return Ok(None);
     }
 
-    fn turn_off(&self) -> bool {
+    fn turn_off(&self) -> Result<Option<bool>, String> {
         Log::log(LogLevel::INFO, "Turning off screen");
-        return true;
+        return Ok(None);
     }
 
     fn should_clear_screen(&self) -> bool { true }
 }
 
-pub fn get_screen_control() -> Box<dyn ScreenControl> {
-    if Path::new(TVSERVICE_PATH).exists() {
-        return Box::new(TvServiceScreenControl{});
+// Switches the screen on a worker thread after delay and reports the outcome and, when verifying,
+// the queried power state back as a "screen" report. Switches are serialized and a newer request supersedes pending ones.
+pub fn switch_screen(config: &Config, on: bool, delay: Duration, feedback: Sender<SensorReport>) {
+    let config = config.clone();
+    let generation = SWITCH_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    thread::spawn(move || {
+        thread::sleep(delay);
+        let _guard = SWITCH_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if SWITCH_GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+
+        let _ = feedback.send(switch(get_screen_control(&config).as_ref(), on));
+    });
+}
+
+fn switch(control: &dyn ScreenControl, on: bool) -> SensorReport {
+    let result = if on { control.turn_on() } else { control.turn_off() };
+    let power = result.as_ref().ok().copied().flatten();
+
+    screen_report(result.map(|_| ()), power)
+}
+
+fn screen_report(result: Result<(), String>, power: Option<bool>) -> SensorReport {
+    let mut sensors = HashMap::new();
+
+    sensors.insert("power".to_string(), power.map_or("unknown", on_off).to_string());
+    if let Err(error) = result {
+        sensors.insert("error".to_string(), error);
+    }
+
+    SensorReport {
+        reporter: "sensorpanel".to_string(),
+        topic: "screen".to_string(),
+        sensors
+    }
+}
+
+// The screen control of the current config, rebuilt only when the config is replaced so frames
+// don't probe backends and backlight devices each time.
+pub struct CachedScreenControl {
+    config: Arc<Config>,
+    control: Box<dyn ScreenControl>
+}
+
+impl CachedScreenControl {
+    pub fn build(config: &Arc<Config>) -> CachedScreenControl {
+        CachedScreenControl { config: config.clone(), control: get_screen_control(config) }
+    }
+
+    pub fn get(&mut self, config: &Arc<Config>) -> &dyn ScreenControl {
+        if !Arc::ptr_eq(&self.config, config) {
+            *self = CachedScreenControl::build(config);
+        }
+
+        self.control.as_ref()
+    }
+}
+
+pub fn get_screen_control(config: &Config) -> Box<dyn ScreenControl> {
+    let mut control = power_control(&config.screen_control);
+    let backlight = config.brightness.as_ref().and_then(|b| b.backlight.as_ref());
+
+    control = match backlight.and_then(|name| backlight_device(name)) {
+        Some(device) if !control.has_hardware_brightness() => Box::new(BacklightBrightness { inner: control, device }),
+        _ => control
+    };
+
+    // Incomplete commands are reported by check_values
+    match config.usb_power.as_ref().and_then(usb_power_commands) {
+        Some((on, off)) => Box::new(UsbPowerControl { inner: control, on, off, retry: RetryPolicy::from_config(&config.screen_control) }),
+        None => control
+    }
+}
+
+fn power_control(screen_config: &ScreenControlConfig) -> Box<dyn ScreenControl> {
+    match screen_config.backend {
+        ScreenBackend::AUTO if Path::new(TVSERVICE_PATH).exists() => Box::new(tvservice_control(screen_config)),
+        ScreenBackend::AUTO => Box::new(SoftwareScreenControl {}),
+        ScreenBackend::TVSERVICE => Box::new(tvservice_control(screen_config)),
+        ScreenBackend::VCGENCMD => Box::new(vcgencmd_control(screen_config)),
+        ScreenBackend::XSET => Box::new(xset_control(screen_config)),
+        ScreenBackend::CEC => Box::new(cec_control(screen_config)),
+        ScreenBackend::BACKLIGHT => Box::new(BacklightScreenControl {
+            device: screen_config.backlight.clone().unwrap_or(DEFAULT_BACKLIGHT.to_string()),
+            retry: RetryPolicy::from_config(screen_config)
+        }),
+        ScreenBackend::COMMAND => match custom_control(screen_config) {
+            Some(control) => Box::new(control),
+            None => {
+                Log::log(LogLevel::ERROR, "Command screen control needs on_command and off_command, using software control");
+                Box::new(SoftwareScreenControl {})
+            }
+        },
+        ScreenBackend::SOFTWARE => Box::new(SoftwareScreenControl {})
+    }
+}
+
+fn program(screen_config: &ScreenControlConfig, default_path: &str) -> String {
+    screen_config.path.clone().unwrap_or(default_path.to_string())
+}
+
+fn tvservice_control(screen_config: &ScreenControlConfig) -> CommandScreenControl {
+    let tvservice = program(screen_config, TVSERVICE_PATH);
+
+    CommandScreenControl {
+        on: vec![ScreenCommand::new(&tvservice, &["-p"])],
+        off: vec![ScreenCommand::new(&tvservice, &["-o"])],
+        status: Some(StatusQuery::new(ScreenCommand::new(&tvservice, &["-s"]), "TV is off", false)),
+        retry: RetryPolicy::from_config(screen_config),
+        clear_screen: false
+    }
+}
+
+fn vcgencmd_control(screen_config: &ScreenControlConfig) -> CommandScreenControl {
+    let vcgencmd = program(screen_config, VCGENCMD_PATH);
+
+    CommandScreenControl {
+        on: vec![ScreenCommand::new(&vcgencmd, &["display_power", "1"])],
+        off: vec![ScreenCommand::new(&vcgencmd, &["display_power", "0"])],
+        status: Some(StatusQuery::new(ScreenCommand::new(&vcgencmd, &["display_power"]), "display_power=1", true)),
+        retry: RetryPolicy::from_config(screen_config),
+        clear_screen: false
+    }
+}
+
+fn xset_control(screen_config: &ScreenControlConfig) -> CommandScreenControl {
+    let xset = program(screen_config, XSET_PATH);
+    let display = vec![("DISPLAY".to_string(), screen_config.display.clone().unwrap_or(":0".to_string()))];
+    let with_display = |mut command: ScreenCommand| { command.env = display.clone(); command };
+
+    CommandScreenControl {
+        on: vec![with_display(ScreenCommand::new(&xset, &["dpms", "force", "on"]))],
+        off: vec![with_display(ScreenCommand::new(&xset, &["dpms", "force", "off"]))],
+        status: Some(StatusQuery::new(with_display(ScreenCommand::new(&xset, &["q"])), "Monitor is On", true)),
+        retry: RetryPolicy::from_config(screen_config),
+        clear_screen: false
+    }
+}
+
+fn cec_control(screen_config: &ScreenControlConfig) -> CommandScreenControl {
+    let cec_client = program(screen_config, CEC_CLIENT_PATH);
+    let address = screen_config.cec_address.clone().unwrap_or("0".to_string());
+    let with_input = |input: String| {
+        let mut command = ScreenCommand::new(&cec_client, &["-s", "-d", "1"]);
+        command.stdin = Some(input);
+        command
+    };
+
+    CommandScreenControl {
+        on: vec![with_input(format!("on {}\n", address))],
+        off: vec![with_input(format!("standby {}\n", address))],
+        status: Some(StatusQuery::new(with_input(format!("pow {}\n", address)), "power status: on", true)),
+        retry: RetryPolicy::from_config(screen_config),
+        clear_screen: false
     }
+}
+
+fn custom_control(screen_config: &ScreenControlConfig) -> Option<CommandScreenControl> {
+    let on = ScreenCommand::from_vec(screen_config.on_command.as_ref()?)?;
+    let off = ScreenCommand::from_vec(screen_config.off_command.as_ref()?)?;
+
+    let status = match (&screen_config.status_command, &screen_config.status_on) {
+        (Some(command), Some(pattern)) => ScreenCommand::from_vec(command).map(|c| StatusQuery::new(c, pattern, true)),
+        _ => None
+    };
+
+    Some(CommandScreenControl {
+        on: vec![on],
+        off: vec![off],
+        status,
+        retry: RetryPolicy::from_config(screen_config),
+        clear_screen: screen_config.clear_screen.unwrap_or(false)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::PathBuf;
+    use std::process;
+
+    struct Stub {
+        dir: PathBuf
+    }
+
+    impl Stub {
+        // Writes an executable that records its arguments, DISPLAY and stdin.
+        fn new(name: &str) -> Stub {
+            let dir = env::temp_dir().join(format!("sensorpanel-{}-{}", name, process::id()));
+            fs::create_dir_all(&dir).unwrap();
+
+            let script = dir.join("stub");
+            let log = dir.join("log");
+            fs::write(&script, format!("#!/bin/sh\necho \"$* DISPLAY=$DISPLAY\" >> {0}\n[ -t 0 ] || cat >> {0}\n", log.display())).unwrap();
+            fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+            Stub { dir }
+        }
+
+        fn path(&self) -> String {
+            self.dir.join("stub").display().to_string()
+        }
+
+        fn log(&self) -> String {
+            fs::read_to_string(self.dir.join("log")).unwrap_or_default()
+        }
+    }
+
+    impl Drop for Stub {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn screen_config(toml_str: &str) -> ScreenControlConfig {
+        let config: Config = toml::from_str(toml_str).unwrap();
+        config.screen_control
+    }
+
+    fn run_all(commands: &[ScreenCommand]) {
+        run_commands(commands).unwrap();
+    }
+
+    fn retry(retries: u32, verify: bool) -> RetryPolicy {
+        RetryPolicy { retries, delay: Duration::from_millis(0), verify }
+    }
+
+    #[test]
+    fn vcgencmd_toggles_display_power() {
+        let stub = Stub::new("vcgencmd");
+        let control = vcgencmd_control(&screen_config(&format!("[screen_control]\nbackend = \"vcgencmd\"\npath = \"{}\"", stub.path())));
+
+        run_all(&control.on);
+        run_all(&control.off);
+
+        assert_eq!(stub.log(), "display_power 1 DISPLAY=\ndisplay_power 0 DISPLAY=\n");
+    }
+
+    #[test]
+    fn xset_forces_dpms_on_configured_display() {
+        let stub = Stub::new("xset");
+        let control = xset_control(&screen_config(&format!("[screen_control]\nbackend = \"xset\"\npath = \"{}\"\ndisplay = \":1\"", stub.path())));
 
-    return Box::new(SoftwareScreenControl {});
-}
\ No newline at end of file
+        run_all(&control.on);
+        run_all(&control.off);
+
+        assert_eq!(stub.log(), "dpms force on DISPLAY=:1\ndpms force off DISPLAY=:1\n");
+    }
+
+    #[test]
+    fn cec_client_receives_commands_on_stdin() {
+        let stub = Stub::new("cec");
+        let control = cec_control(&screen_config(&format!("[screen_control]\nbackend = \"cec\"\npath = \"{}\"\ncec_address = \"4\"", stub.path())));
+
+        run_all(&control.on);
+        run_all(&control.off);
+
+        assert_eq!(stub.log(), "-s -d 1 DISPLAY=\non 4\n-s -d 1 DISPLAY=\nstandby 4\n");
+    }
+
+    #[test]
+    fn tvservice_powers_hdmi() {
+        let stub = Stub::new("tvservice");
+        let control = tvservice_control(&screen_config(&format!("[screen_control]\nbackend = \"tvservice\"\npath = \"{}\"", stub.path())));
+
+        run_all(&control.on);
+        run_all(&control.off);
+
+        assert_eq!(stub.log(), "-p DISPLAY=\n-o DISPLAY=\n");
+    }
+
+    #[test]
+    fn custom_commands_run_as_configured() {
+        let stub = Stub::new("custom");
+        let control = custom_control(&screen_config(&format!("[screen_control]\nbackend = \"command\"\non_command = [\"{0}\", \"wake\"]\noff_command = [\"{0}\", \"sleep\", \"now\"]\nclear_screen = true", stub.path()))).unwrap();
+
+        run_all(&control.on);
+        run_all(&control.off);
+
+        assert_eq!(stub.log(), "wake DISPLAY=\nsleep now DISPLAY=\n");
+        assert!(control.should_clear_screen());
+    }
+
+    #[test]
+    fn custom_control_requires_both_commands() {
+        assert!(custom_control(&screen_config("[screen_control]\nbackend = \"command\"\non_command = [\"true\"]")).is_none());
+        assert!(custom_control(&screen_config("[screen_control]\nbackend = \"command\"\non_command = []\noff_command = [\"true\"]")).is_none());
+    }
+
+    #[test]
+    fn backlight_writes_bl_power() {
+        let stub = Stub::new("backlight");
+        let control = BacklightScreenControl { device: stub.dir.display().to_string(), retry: retry(0, true) };
+
+        assert!(control.turn_off().is_ok());
+        assert_eq!(fs::read_to_string(stub.dir.join("bl_power")).unwrap(), "1");
+        assert_eq!(control.query_power(), Some(false));
+        assert!(control.turn_on().is_ok());
+        assert_eq!(fs::read_to_string(stub.dir.join("bl_power")).unwrap(), "0");
+        assert_eq!(control.query_power(), Some(true));
+    }
+
+    #[test]
+    fn backlight_brightness_scales_to_max_brightness() {
+        let stub = Stub::new("brightness");
+        fs::write(stub.dir.join("max_brightness"), "255\n").unwrap();
+        let device = stub.dir.display().to_string();
+        let config: Config = toml::from_str(&format!("[screen_control]\nbackend = \"software\"\n[brightness]\nbacklight = \"{}\"", device)).unwrap();
+        let control = get_screen_control(&config);
+
+        assert!(control.has_hardware_brightness());
+        assert!(control.set_brightness(50));
+        assert_eq!(fs::read_to_string(stub.dir.join("brightness")).unwrap(), "128");
+        assert!(control.should_clear_screen());
+    }
+
+    #[test]
+    fn brightness_falls_back_to_software_dimming() {
+        let control = get_screen_control(&toml::from_str("[screen_control]\nbackend = \"software\"").unwrap());
+
+        assert!(!control.has_hardware_brightness());
+        assert!(!control.set_brightness(50));
+    }
+
+    fn usb_config(toml_str: &str) -> UsbPowerConfig {
+        let config: Config = toml::from_str(&format!("[usb_power]\n{}", toml_str)).unwrap();
+        config.usb_power.unwrap()
+    }
+
+    #[test]
+    fn uhubctl_uses_configured_location_ports_and_retries() {
+        let stub = Stub::new("uhubctl");
+        let (on, off) = usb_power_commands(&usb_config(&format!("path = \"{}\"\nlocation = \"1-1\"\nports = \"2,3\"\nretries = 100", stub.path()))).unwrap();
+
+        run_all(&[on, off]);
+
+        assert_eq!(stub.log(), "-l 1-1 -p 2,3 -r 100 -a 1 DISPLAY=\n-l 1-1 -p 2,3 -r 100 -a 0 DISPLAY=\n");
+    }
+
+    #[test]
+    fn usb_power_accepts_custom_commands() {
+        let (on, off) = usb_power_commands(&usb_config("on_command = [\"hub\", \"up\"]\noff_command = [\"hub\", \"down\"]")).unwrap();
+
+        assert_eq!(on, ScreenCommand::new("hub", &["up"]));
+        assert_eq!(off, ScreenCommand::new("hub", &["down"]));
+        assert_eq!(usb_power_commands(&usb_config("")).unwrap().0, ScreenCommand::new("uhubctl", &["-a", "1"]));
+    }
+
+    #[test]
+    fn usb_power_retries_the_hub_command() {
+        let stub = Stub::new("hub");
+        let attempts = stub.dir.join("attempts");
+        let on = ScreenCommand::new("sh", &["-c", &format!("echo x >> {0}; [ $(wc -l < {0}) -ge 2 ]", attempts.display())]);
+        let control = UsbPowerControl { inner: Box::new(SoftwareScreenControl {}), on: on.clone(), off: on, retry: retry(1, false) };
+
+        assert_eq!(control.turn_on(), Ok(None));
+        assert_eq!(fs::read_to_string(&attempts).unwrap(), "x\nx\n");
+    }
+
+    #[test]
+    fn failing_command_reports_exit_status_and_stderr() {
+        let error = run_command(&ScreenCommand::new("sh", &["-c", "echo no display >&2; exit 3"])).unwrap_err();
+
+        assert!(error.contains("exit status: 3"), "{}", error);
+        assert!(error.ends_with("no display"), "{}", error);
+        assert!(run_command(&ScreenCommand::new("/nonexistent/tvservice", &[])).is_err());
+    }
+
+    #[test]
+    fn retries_until_switch_succeeds() {
+        let attempts = std::cell::Cell::new(0);
+        let switch = || { attempts.set(attempts.get() + 1); if attempts.get() < 3 { Err("busy".to_string()) } else { Ok(()) } };
+
+        assert!(retry(2, false).run(true, switch, || None).is_ok());
+        assert_eq!(attempts.get(), 3);
+
+        attempts.set(0);
+        assert_eq!(retry(1, false).run(true, switch, || None), Err("busy".to_string()));
+        assert_eq!(attempts.get(), 2);
+    }
+
+    #[test]
+    fn verification_fails_when_power_does_not_match() {
+        assert!(retry(1, true).run(true, || Ok(()), || Some(false)).is_err());
+        assert!(retry(0, false).run(true, || Ok(()), || Some(false)).is_ok());
+        assert!(retry(0, true).run(true, || Ok(()), || None).is_ok());
+    }
+
+    #[test]
+    fn queries_power_only_when_verifying() {
+        let queries = std::cell::Cell::new(0);
+        let query = || { queries.set(queries.get() + 1); Some(true) };
+
+        assert!(retry(0, false).run(true, || Ok(()), query).is_ok());
+        assert_eq!(queries.get(), 0);
+        assert!(retry(0, true).run(true, || Ok(()), query).is_ok());
+        assert_eq!(queries.get(), 1);
+    }
+
+    #[test]
+    fn switch_queries_power_only_when_verifying() {
+        let stub = Stub::new("status");
+        let config = |verify: bool| screen_config(&format!("[screen_control]\nbackend = \"command\"\non_command = [\"true\"]\noff_command = [\"true\"]\n\
+                                                            status_command = [\"{}\", \"status\"]\nstatus_on = \"on\"\nverify = {}", stub.path(), verify));
+
+        let report = switch(&custom_control(&config(false)).unwrap(), false);
+        assert_eq!(report.sensors["power"], "unknown");
+        assert_eq!(stub.log(), "");
+
+        let report = switch(&custom_control(&config(true)).unwrap(), false);
+        assert_eq!(report.sensors["power"], "off");
+        assert_eq!(stub.log(), "status DISPLAY=\n");
+    }
+
+    #[test]
+    fn status_query_matches_output_pattern() {
+        let query = |output: &str, pattern: &str, on_match: bool| StatusQuery::new(ScreenCommand::new("echo", &[output]), pattern, on_match).run();
+
+        assert_eq!(query("display_power=1", "display_power=1", true), Some(true));
+        assert_eq!(query("display_power=0", "display_power=1", true), Some(false));
+        assert_eq!(query("state 0x120002 [TV is off]", "TV is off", false), Some(false));
+        assert_eq!(StatusQuery::new(ScreenCommand::new("false", &[]), "on", true).run(), None);
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::data::SensorData;
+
+// The report history grouped by reporter once per frame, so panels and widgets look reports up
+// instead of filtering the whole history each time.
+pub struct Sensors<'a> {
+    by_reporter: HashMap<&'a str, Vec<&'a SensorData>>,
+    empty: Vec<&'a SensorData>
+}
+
+impl<'a> Sensors<'a> {
+    pub fn index(data: &'a [Arc<SensorData>]) -> Sensors<'a> {
+        let mut by_reporter: HashMap<&'a str, Vec<&'a SensorData>> = HashMap::new();
+        for report in data.iter() {
+            by_reporter.entry(report.reporter.as_str()).or_default().push(report.as_ref());
+        }
+
+        Sensors { by_reporter, empty: Vec::new() }
+    }
+
+    // Reports of the reporter, oldest first.
+    pub fn history(&self, reporter: &str) -> &Vec<&'a SensorData> {
+        self.by_reporter.get(reporter).unwrap_or(&self.empty)
+    }
+
+    pub fn has_data(&self, reporter: &str) -> bool {
+        self.by_reporter.contains_key(reporter)
+    }
+
+    pub fn latest(&self, reporter: &str) -> Option<&'a SensorData> {
+        self.history(reporter).last().copied()
+    }
+
+    pub fn value(&self, reporter: &str, key: &str) -> Option<&'a str> {
+        self.latest(reporter).and_then(|latest| latest.values.get(key)).map(|value| value.as_str())
+    }
+
+    pub fn number(&self, reporter: &str, key: &str) -> Option<f32> {
+        self.value(reporter, key).and_then(|value| value.parse::<f32>().ok())
+    }
+
+    // Reporters in the history, sorted by name.
+    pub fn reporters(&self) -> Vec<&'a str> {
+        let mut reporters: Vec<&'a str> = self.by_reporter.keys().copied().collect();
+        reporters.sort_unstable();
+        reporters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn report(reporter: &str, values: Vec<(&str, &str)>, received: Instant) -> Arc<SensorData> {
+        let values = values.into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        Arc::new(SensorData { reporter: reporter.to_string(), values, received })
+    }
+
+    #[test]
+    fn groups_reports_by_reporter_in_order() {
+        let now = Instant::now();
+        let data = vec![
+            report("linux-sensor-agent", vec![("cpu_utilization", "20")], now),
+            report("hue-sensor-agent", vec![("hue_presence", "true")], now),
+            report("linux-sensor-agent", vec![("cpu_utilization", "30.5"), ("cpu_model", "5950X")], now)
+        ];
+        let sensors = Sensors::index(&data);
+
+        assert_eq!(sensors.reporters(), vec!["hue-sensor-agent", "linux-sensor-agent"]);
+        assert_eq!(sensors.history("linux-sensor-agent").len(), 2);
+        assert!(sensors.history("windows-sensor-agent").is_empty());
+        assert!(!sensors.has_data("windows-sensor-agent"));
+        assert_eq!(sensors.number("linux-sensor-agent", "cpu_utilization"), Some(30.5));
+        assert_eq!(sensors.value("linux-sensor-agent", "cpu_model"), Some("5950X"));
+        assert_eq!(sensors.number("linux-sensor-agent", "cpu_model"), None);
+    }
+}
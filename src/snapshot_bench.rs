@@ -0,0 +1,122 @@
+//! Timing comparison between the previous mutex-based state sharing and the
+//! snapshot publishing used now. Both designs handle each message with
+//! Event::handle and draw each frame with rule_panel and select_panel, so they
+//! only differ in how the state is shared. Frames are measured while another
+//! thread keeps handling messages, as the websocket thread does, and are paced
+//! like a render loop. Run with:
+//!
+//! cargo test --release --features x86 -- --ignored --nocapture snapshot_benchmark
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::sync::{Arc, Mutex, Barrier};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Instant, Duration};
+use arc_swap::ArcSwap;
+use crate::clock::{Clock, SystemClock};
+use crate::config::Config;
+use crate::data::SensorData;
+use crate::event::{Event, EventExt};
+use crate::panel::{rule_panel, select_panel};
+use crate::state::{State, StateExt};
+use crate::websocket::SensorReport;
+
+const HISTORY: usize = 500;
+const ITERATIONS: u32 = 500;
+const FRAMES: u32 = 200;
+const FRAME_INTERVAL: Duration = Duration::from_millis(1);
+
+fn sensor_values() -> HashMap<String, String> {
+    (1..=60).map(|i| (format!("sensor_key_{}", i), format!("{}.5", i))).collect()
+}
+
+fn sensor_data(now: Instant) -> SensorData {
+    SensorData { reporter: "linux-sensor-agent".to_string(), values: sensor_values(), received: now }
+}
+
+fn sensor_report() -> SensorReport {
+    SensorReport { reporter: "linux-sensor-agent".to_string(), topic: "sensors".to_string(), sensors: sensor_values() }
+}
+
+fn time_per_op<F: FnMut()>(mut op: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        op();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn with_background_writer<W: FnMut() + Send + 'static, F: FnMut()>(mut writer: W, mut frame: F) -> (Duration, Duration) {
+    let stop = Arc::new(AtomicBool::new(false));
+    let started = Arc::new(Barrier::new(2));
+    let thread_stop = Arc::clone(&stop);
+    let thread_started = Arc::clone(&started);
+    let handle = thread::spawn(move || {
+        writer();
+        thread_started.wait();
+        while !thread_stop.load(Ordering::Relaxed) {
+            writer();
+        }
+    });
+
+    started.wait();
+    let frame_times = (0..FRAMES).map(|_| {
+        thread::sleep(FRAME_INTERVAL);
+        let start = Instant::now();
+        frame();
+        start.elapsed()
+    }).collect::<Vec<Duration>>();
+
+    stop.store(true, Ordering::Relaxed);
+    handle.join().unwrap();
+
+    (frame_times.iter().sum::<Duration>() / FRAMES, *frame_times.iter().max().unwrap())
+}
+
+// Previous design: one mutex, held while a message is handled and while a
+// frame reads the state.
+fn before_message(locked: &Mutex<State>, config: &Config, clock: &dyn Clock) {
+    let mut guard = locked.lock().unwrap();
+    *guard = Event::handle(sensor_report(), &guard, config, &[], clock);
+}
+
+fn before_frame(locked: &Mutex<State>, config: &Config, now: Instant) {
+    let guard = locked.lock().unwrap();
+    black_box((rule_panel(config, &guard.sensor_data, now), select_panel(&guard)));
+}
+
+// Snapshots: the handler derives a new state from the latest one and publishes it.
+fn after_message(snapshots: &ArcSwap<State>, config: &Config, clock: &dyn Clock) {
+    let state = snapshots.load_full();
+    snapshots.store(Arc::new(Event::handle(sensor_report(), &state, config, &[], clock)));
+}
+
+fn after_frame(snapshots: &ArcSwap<State>, config: &Config, now: Instant) {
+    let state = snapshots.load();
+    black_box((rule_panel(config, &state.sensor_data, now), select_panel(&state)));
+}
+
+#[test]
+#[ignore]
+fn snapshot_benchmark() {
+    let now = SystemClock().now();
+    let config = Arc::new(toml::from_str::<Config>("").unwrap());
+
+    let mut state = State::init(now);
+    state.sensor_data = (0..HISTORY).map(|_| Arc::new(sensor_data(now))).collect();
+    let locked = Arc::new(Mutex::new(state.clone()));
+    let snapshots = Arc::new(ArcSwap::from_pointee(state));
+
+    let before_per_message = time_per_op(|| before_message(&locked, &config, &SystemClock()));
+    let after_per_message = time_per_op(|| after_message(&snapshots, &config, &SystemClock()));
+
+    let (writer_lock, writer_config) = (Arc::clone(&locked), Arc::clone(&config));
+    let before_per_frame = with_background_writer(move || before_message(&writer_lock, &writer_config, &SystemClock()), || before_frame(&locked, &config, now));
+
+    let (writer_snapshots, writer_config) = (Arc::clone(&snapshots), Arc::clone(&config));
+    let after_per_frame = with_background_writer(move || after_message(&writer_snapshots, &writer_config, &SystemClock()), || after_frame(&snapshots, &config, now));
+
+    println!("per message:                  before {:?}, after {:?}", before_per_message, after_per_message);
+    println!("per frame with message load:  before {:?} (worst {:?}), after {:?} (worst {:?})", before_per_frame.0, before_per_frame.1, after_per_frame.0, after_per_frame.1);
+}
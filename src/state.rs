@@ -1,10 +1,15 @@
 use crate::data::{SensorData};
-use std::time::SystemTime;
+use std::time::{Instant, Duration};
 use crate::log::{Log, LogExt, LogLevel};
+use crate::presence::SourceReading;
+use crate::config::{ScheduleMode, CarouselPage, CarouselConfig};
+use std::collections::HashMap;
+use std::sync::Arc;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum ScreenState {
     OFF,
+    ON,
     AUTO
 }
 
@@ -18,43 +23,96 @@ pub enum Present {
 #[derive(Clone, Debug)]
 pub struct PresenceData {
     pub present: Present,
-    pub last_switch_to_false: SystemTime
+    pub last_switch_to_false: Instant,
+    pub sources: HashMap<String, SourceReading>
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Severity {
+    INFO,
+    WARNING,
+    ERROR
+}
+
+#[derive(Clone, Debug)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+    pub severity: Severity,
+    pub expires: Instant
+}
+
+const MAX_NOTIFICATIONS: usize = 5;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CarouselState {
+    pub page: usize,
+    pub pages: usize,
+    pub panel: String,
+    pub page_since: Instant,
+    pub paused_since: Option<Instant>
 }
 
 #[derive(Clone, Debug)]
 pub struct State {
-    pub sensor_data: Vec<SensorData>,
+    pub sensor_data: Vec<Arc<SensorData>>,
     pub screen_on: bool,
     pub screen_state: ScreenState,
-    pub presence: PresenceData
+    pub screen_power: Option<bool>,
+    pub screen_switched_at: Instant,
+    pub screen_error: Option<String>,
+    pub presence: PresenceData,
+    pub keep_on_until: Option<Instant>,
+    pub panel_override: Option<String>,
+    pub active_panel: String,
+    pub panel_candidate: Option<(String, Instant)>,
+    pub carousel: Option<CarouselState>,
+    pub brightness: u8,
+    pub manual_brightness: bool,
+    pub notifications: Vec<Notification>,
+    pub config_generation: u32,
+    pub schedule_mode: Option<ScheduleMode>,
+    pub last_host_activity: Instant,
+    pub hosts_idle: bool
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Action {
     ScreenOn,
-    ScreenOff
+    ScreenOff,
+    ScreenAuto,
+    ToggleScreen,
+    ShowPanel(String),
+    NextPanel,
+    SetBrightness(u8),
+    AutoBrightness,
+    ReloadConfig,
+    ShowMessage(String, u32),
+    KeepOnFor(u32),
+    PauseCarousel,
+    ResumeCarousel,
+    ToggleCarousel
 }
 
 pub trait StateExt {
-    fn transfer_to(self: Self, _: &mut Self);
-    fn update_presence(self: &Self, present: bool, presence_threshold_secs: u32) -> Self;
-    fn toggle_screen_state(self: &Self) -> Self;
+    fn update_presence(self: Self, present: bool, presence_threshold_secs: u32, now: Instant) -> Self;
+    fn toggle_screen_state(self: &Self, now: Instant) -> Self;
+    fn apply_action(self: &Self, action: &Action, panels: &[String], now: Instant) -> Self;
+    fn push_notification(self: &Self, title: &str, body: &str, severity: Severity, ttl_secs: u32, now: Instant) -> Self;
+    fn apply_schedule(self: Self, mode: Option<ScheduleMode>, now: Instant) -> Self;
+    fn apply_hosts_idle(self: Self, idle: bool, now: Instant) -> Self;
+    fn apply_auto_brightness(self: Self, level: Option<u8>) -> Self;
+    fn apply_panel_selection(self: Self, panel: String, switch_delay: Duration, now: Instant) -> Self;
+    fn apply_carousel(self: Self, pages: &[CarouselPage], config: &CarouselConfig, now: Instant) -> Self;
     fn state_change_actions(self: &Self, other: &Self) -> Vec<Action>;
-    fn init() -> Self;
+    fn init(now: Instant) -> Self;
 }
 
 impl StateExt for State {
-    fn transfer_to(self: Self, mut other: &mut State) {
-        other.presence = self.presence;
-        other.screen_state = self.screen_state;
-        other.screen_on = self.screen_on;
-        other.sensor_data = self.sensor_data;
-    }
-
-    fn update_presence(self: &State, present: bool, presence_threshold_secs: u32) -> State {
-        let current_time = SystemTime::now();
-        let duration_since_switch_to_false = current_time.duration_since(self.presence.last_switch_to_false).unwrap();
-        let mut new_state = self.clone();
+    fn update_presence(self: State, present: bool, presence_threshold_secs: u32, now: Instant) -> State {
+        let duration_since_switch_to_false = now.saturating_duration_since(self.presence.last_switch_to_false);
+        let previous = self.presence.present.clone();
+        let mut new_state = self;
         let mut new_presence = &mut new_state.presence;
 
         if present && new_presence.present == Present::NO {
@@ -63,33 +121,217 @@ impl StateExt for State {
             new_presence.present = Present::NO;
         } else if !present && new_presence.present == Present::YES {
             new_presence.present = Present::PENDING;
-            new_presence.last_switch_to_false = SystemTime::now();
+            new_presence.last_switch_to_false = now;
         } else if present {
             new_presence.present = Present::YES;
         }
 
-        if self.presence.present != new_presence.present {
+        if previous != new_presence.present {
             Log::log(LogLevel::TRACE, &*format!("Presence transitioned to {:?}", new_presence.present));
         }
 
-        new_state.screen_on = new_state.screen_state == ScreenState::AUTO && new_presence.present != Present::NO;
+        new_state.screen_on = new_state.should_screen_be_on(now);
 
         return new_state;
     }
 
-    fn toggle_screen_state(self: &State) -> State {
+    fn toggle_screen_state(self: &State, now: Instant) -> State {
         let mut new_state = self.clone();
 
         new_state.screen_state = match self.screen_state {
             ScreenState::OFF => ScreenState::AUTO,
+            ScreenState::ON => ScreenState::OFF,
             ScreenState::AUTO => if self.screen_on { ScreenState::OFF } else { ScreenState::AUTO }
         };
+        new_state.keep_on_until = None;
+
+        new_state.screen_on = new_state.should_screen_be_on(now);
+
+        return new_state;
+    }
+
+    fn apply_action(self: &State, action: &Action, panels: &[String], now: Instant) -> State {
+        let mut new_state = match action {
+            Action::ToggleScreen => return self.toggle_screen_state(now),
+            _ => self.clone()
+        };
+
+        match action {
+            Action::ScreenOn => {
+                new_state.screen_state = ScreenState::ON;
+            }
+            Action::ScreenOff => {
+                new_state.screen_state = ScreenState::OFF;
+                new_state.keep_on_until = None;
+            }
+            Action::ScreenAuto => {
+                new_state.screen_state = ScreenState::AUTO;
+            }
+            Action::ShowPanel(name) => {
+                if name == "auto" {
+                    new_state.panel_override = None;
+                } else if panels.contains(name) {
+                    new_state.panel_override = Some(name.clone());
+                } else {
+                    Log::log(LogLevel::ERROR, &*format!("Unknown panel {}", name));
+                }
+            }
+            Action::NextPanel => {
+                let next_index = match &self.panel_override {
+                    Some(name) => panels.iter().position(|p| p == name).map_or(0, |i| (i + 1) % panels.len()),
+                    None => 0
+                };
+                new_state.panel_override = panels.get(next_index).cloned();
+            }
+            Action::SetBrightness(level) => {
+                new_state.brightness = *level.min(&100);
+                new_state.manual_brightness = true;
+            }
+            Action::AutoBrightness => {
+                new_state.manual_brightness = false;
+            }
+            Action::ReloadConfig => {
+                new_state.config_generation = self.config_generation.wrapping_add(1);
+            }
+            Action::ShowMessage(text, ttl_secs) => {
+                new_state = new_state.push_notification(text, "", Severity::INFO, *ttl_secs, now);
+            }
+            Action::KeepOnFor(secs) => {
+                new_state.keep_on_until = Some(now + Duration::from_secs(*secs as u64));
+            }
+            Action::PauseCarousel | Action::ResumeCarousel | Action::ToggleCarousel => {
+                match new_state.carousel.as_mut() {
+                    Some(carousel) => {
+                        let paused = match action {
+                            Action::PauseCarousel => true,
+                            Action::ResumeCarousel => false,
+                            _ => carousel.paused_since.is_none()
+                        };
+                        pause_carousel(carousel, paused, now);
+                    }
+                    None => Log::log(LogLevel::INFO, "Carousel is not enabled, no carousel_pages configured")
+                }
+            }
+            Action::ToggleScreen => {}
+        }
+
+        new_state.screen_on = new_state.should_screen_be_on(now);
+
+        return new_state;
+    }
+
+    fn push_notification(self: &State, title: &str, body: &str, severity: Severity, ttl_secs: u32, now: Instant) -> State {
+        let mut new_state = self.clone();
+
+        new_state.notifications.retain(|n| n.expires > now);
+        new_state.notifications.push(Notification {
+            title: title.to_string(),
+            body: body.to_string(),
+            severity,
+            expires: now + Duration::from_secs(ttl_secs as u64)
+        });
+
+        if new_state.notifications.len() > MAX_NOTIFICATIONS {
+            new_state.notifications.remove(0);
+        }
+
+        return new_state;
+    }
+
+    fn apply_schedule(self: State, mode: Option<ScheduleMode>, now: Instant) -> State {
+        let mut new_state = self;
+
+        if new_state.schedule_mode != mode {
+            Log::log(LogLevel::INFO, &*format!("Schedule mode changed to {:?}", mode));
+        }
+
+        new_state.schedule_mode = mode;
+        new_state.screen_on = new_state.should_screen_be_on(now);
+
+        return new_state;
+    }
+
+    fn apply_hosts_idle(self: State, idle: bool, now: Instant) -> State {
+        let mut new_state = self;
+
+        if new_state.hosts_idle != idle {
+            Log::log(LogLevel::INFO, &*format!("Monitored hosts {}", if idle { "went idle" } else { "are active again" }));
+        }
+
+        new_state.hosts_idle = idle;
+        new_state.screen_on = new_state.should_screen_be_on(now);
+
+        return new_state;
+    }
+
+    fn apply_auto_brightness(self: State, level: Option<u8>) -> State {
+        let mut new_state = self;
+
+        if let (false, Some(level)) = (new_state.manual_brightness, level) {
+            new_state.brightness = level.min(100);
+        }
+
+        return new_state;
+    }
+
+    // Switches to the selected panel once it has been selected for switch_delay, so late reports
+    // don't make the display flap between panels.
+    fn apply_panel_selection(self: State, panel: String, switch_delay: Duration, now: Instant) -> State {
+        let mut new_state = self;
+
+        if panel == new_state.active_panel {
+            new_state.panel_candidate = None;
+            return new_state;
+        }
+
+        let since = match &new_state.panel_candidate {
+            Some((candidate, since)) if *candidate == panel => *since,
+            _ => now
+        };
+
+        if now.saturating_duration_since(since) >= switch_delay {
+            Log::log(LogLevel::INFO, &*format!("Switching panel from {} to {}", new_state.active_panel, panel));
+            new_state.active_panel = panel;
+            new_state.panel_candidate = None;
+        } else {
+            new_state.panel_candidate = Some((panel, since));
+        }
+
+        return new_state;
+    }
+
+    // Moves on to the next page once the current one has been shown for its dwell time. A paused
+    // carousel stays on its page until resumed or until resume_after_secs have passed.
+    fn apply_carousel(self: State, pages: &[CarouselPage], config: &CarouselConfig, now: Instant) -> State {
+        let mut new_state = self;
+
+        if pages.is_empty() {
+            new_state.carousel = None;
+            return new_state;
+        }
 
-        new_state.screen_on = match new_state.screen_state {
-            ScreenState::OFF => { false }
-            ScreenState::AUTO => { self.presence.present != Present::NO }
+        // Pages may have been removed by a config reload
+        let mut carousel = match new_state.carousel.take() {
+            Some(carousel) if carousel.page < pages.len() => carousel,
+            _ => CarouselState { page: 0, pages: pages.len(), panel: String::new(), page_since: now, paused_since: None }
         };
 
+        if let (Some(since), Some(resume_after)) = (carousel.paused_since, config.resume_after_secs) {
+            if now.saturating_duration_since(since) >= Duration::from_secs(resume_after) {
+                pause_carousel(&mut carousel, false, now);
+            }
+        }
+
+        let dwell = Duration::from_secs(pages[carousel.page].dwell_secs.unwrap_or(config.dwell_secs));
+        if carousel.paused_since.is_none() && now.saturating_duration_since(carousel.page_since) >= dwell {
+            carousel.page = (carousel.page + 1) % pages.len();
+            carousel.page_since = now;
+        }
+
+        carousel.pages = pages.len();
+        carousel.panel = pages[carousel.page].panel.clone();
+        new_state.carousel = Some(carousel);
+
         return new_state;
     }
 
@@ -102,18 +344,404 @@ impl StateExt for State {
             actions.push(Action::ScreenOn);
         }
 
+        if self.brightness != previous.brightness {
+            actions.push(Action::SetBrightness(self.brightness));
+        }
+
+        if self.config_generation != previous.config_generation {
+            actions.push(Action::ReloadConfig);
+        }
+
         actions
     }
 
-    fn init() -> State {
+    fn init(now: Instant) -> State {
         State {
             sensor_data: Vec::new(),
             screen_on: true,
             screen_state: ScreenState::AUTO,
+            screen_power: None,
+            screen_switched_at: now,
+            screen_error: None,
             presence: PresenceData {
                 present: Present::YES,
-                last_switch_to_false: SystemTime::now()
-            }
+                last_switch_to_false: now,
+                sources: HashMap::new()
+            },
+            keep_on_until: None,
+            panel_override: None,
+            active_panel: "pending".to_string(),
+            panel_candidate: None,
+            carousel: None,
+            brightness: 100,
+            manual_brightness: false,
+            notifications: Vec::new(),
+            config_generation: 0,
+            schedule_mode: None,
+            last_host_activity: now,
+            hosts_idle: false
+        }
+    }
+}
+
+impl State {
+    fn should_screen_be_on(self: &State, now: Instant) -> bool {
+        let kept_on = self.keep_on_until.is_some_and(|until| until > now);
+
+        match (&self.screen_state, &self.schedule_mode) {
+            (ScreenState::OFF, _) => false,
+            (ScreenState::ON, _) => true,
+            (ScreenState::AUTO, _) if kept_on => true,
+            (ScreenState::AUTO, Some(ScheduleMode::OFF)) => false,
+            (ScreenState::AUTO, Some(ScheduleMode::ON)) => true,
+            (ScreenState::AUTO, _) if self.hosts_idle => false,
+            (ScreenState::AUTO, _) => self.presence.present != Present::NO
+        }
+    }
+}
+
+// Resuming shows the current page for its full dwell time again.
+fn pause_carousel(carousel: &mut CarouselState, paused: bool, now: Instant) {
+    match (paused, carousel.paused_since) {
+        (true, None) => carousel.paused_since = Some(now),
+        (false, Some(_)) => {
+            carousel.paused_since = None;
+            carousel.page_since = now;
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::panel::panel_names;
+    use crate::layout::parse_layout;
+    use std::sync::OnceLock;
+
+    fn t0() -> Instant {
+        static START: OnceLock<Instant> = OnceLock::new();
+        *START.get_or_init(Instant::now)
+    }
+
+    fn secs(secs: u64) -> Instant {
+        t0() + Duration::from_secs(secs)
+    }
+
+    fn panels() -> Vec<String> {
+        panel_names(&HashMap::new())
+    }
+
+    fn absent_state() -> State {
+        let mut state = State::init(t0());
+        state.presence.present = Present::NO;
+        state.screen_on = false;
+        state
+    }
+
+    #[test]
+    fn screen_on_forces_screen_on_without_presence() {
+        let state = absent_state().apply_action(&Action::ScreenOn, &panels(), t0());
+
+        assert_eq!(state.screen_state, ScreenState::ON);
+        assert!(state.screen_on);
+        assert!(state.state_change_actions(&absent_state()) == vec![Action::ScreenOn]);
+    }
+
+    #[test]
+    fn screen_off_forces_screen_off_with_presence() {
+        let state = State::init(t0()).apply_action(&Action::ScreenOff, &panels(), t0());
+
+        assert_eq!(state.screen_state, ScreenState::OFF);
+        assert!(!state.screen_on);
+        assert!(state.state_change_actions(&State::init(t0())) == vec![Action::ScreenOff]);
+    }
+
+    #[test]
+    fn screen_auto_follows_presence() {
+        let state = absent_state().apply_action(&Action::ScreenOn, &panels(), t0()).apply_action(&Action::ScreenAuto, &panels(), t0());
+
+        assert_eq!(state.screen_state, ScreenState::AUTO);
+        assert!(!state.screen_on);
+    }
+
+    #[test]
+    fn toggle_from_forced_on_turns_screen_off() {
+        let state = State::init(t0()).apply_action(&Action::ScreenOn, &panels(), t0()).apply_action(&Action::ToggleScreen, &panels(), t0());
+
+        assert_eq!(state.screen_state, ScreenState::OFF);
+        assert!(!state.screen_on);
+    }
+
+    #[test]
+    fn show_panel_sets_and_clears_override() {
+        let state = State::init(t0()).apply_action(&Action::ShowPanel("linux".to_string()), &panels(), t0());
+        assert_eq!(state.panel_override, Some("linux".to_string()));
+
+        let state = state.apply_action(&Action::ShowPanel("auto".to_string()), &panels(), t0());
+        assert_eq!(state.panel_override, None);
+    }
+
+    #[test]
+    fn show_panel_ignores_unknown_panel() {
+        let state = State::init(t0())
+            .apply_action(&Action::ShowPanel("windows".to_string()), &panels(), t0())
+            .apply_action(&Action::ShowPanel("nonexistent".to_string()), &panels(), t0());
+
+        assert_eq!(state.panel_override, Some("windows".to_string()));
+    }
+
+    #[test]
+    fn next_panel_cycles_through_panels() {
+        let mut state = State::init(t0());
+
+        for expected in panels().iter().chain(panels().iter().take(1)) {
+            state = state.apply_action(&Action::NextPanel, &panels(), t0());
+            assert_eq!(state.panel_override.as_ref(), Some(expected));
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn show_and_next_panel_include_loaded_layouts() {
+        let layouts = vec![("gaming".to_string(), parse_layout("").unwrap())].into_iter().collect();
+        let loaded = panel_names(&layouts);
+        assert_eq!(loaded, vec!["gaming", "linux", "overview", "pending", "windows"]);
+
+        let state = State::init(t0()).apply_action(&Action::ShowPanel("gaming".to_string()), &loaded, t0());
+        assert_eq!(state.panel_override.as_deref(), Some("gaming"));
+
+        let state = state.apply_action(&Action::NextPanel, &loaded, t0());
+        assert_eq!(state.panel_override.as_deref(), Some("linux"));
+
+        let state = State::init(t0()).apply_action(&Action::ShowPanel("gaming".to_string()), &panels(), t0());
+        assert_eq!(state.panel_override, None);
+    }
+
+    #[test]
+    fn set_brightness_clamps_and_emits_action() {
+        let state = State::init(t0()).apply_action(&Action::SetBrightness(150), &panels(), t0());
+        assert_eq!(state.brightness, 100);
+
+        let dimmed = state.apply_action(&Action::SetBrightness(30), &panels(), t0());
+        assert_eq!(dimmed.brightness, 30);
+        assert!(dimmed.state_change_actions(&state) == vec![Action::SetBrightness(30)]);
+    }
+
+    #[test]
+    fn auto_brightness_yields_to_manual_level() {
+        let state = State::init(t0()).apply_auto_brightness(Some(40));
+        assert_eq!(state.brightness, 40);
+
+        let manual = state.apply_action(&Action::SetBrightness(80), &panels(), t0()).apply_auto_brightness(Some(40));
+        assert_eq!(manual.brightness, 80);
+
+        let auto = manual.apply_action(&Action::AutoBrightness, &panels(), t0()).apply_auto_brightness(Some(40));
+        assert_eq!(auto.brightness, 40);
+        assert_eq!(State::init(t0()).apply_auto_brightness(None).brightness, 100);
+    }
+
+    #[test]
+    fn reload_config_emits_action() {
+        let state = State::init(t0()).apply_action(&Action::ReloadConfig, &panels(), t0());
+
+        assert!(state.state_change_actions(&State::init(t0())) == vec![Action::ReloadConfig]);
+    }
+
+    #[test]
+    fn show_message_queues_notification() {
+        let state = State::init(t0()).apply_action(&Action::ShowMessage("Hello".to_string(), 30), &panels(), t0());
+
+        assert_eq!(state.notifications.len(), 1);
+        assert_eq!(state.notifications[0].title, "Hello");
+        assert_eq!(state.notifications[0].severity, Severity::INFO);
+        assert_eq!(state.notifications[0].expires, t0() + Duration::from_secs(30));
+    }
+
+    #[test]
+    fn push_notification_drops_expired_and_oldest() {
+        let mut state = State::init(t0()).push_notification("Expired", "", Severity::INFO, 0, t0());
+
+        for i in 0..MAX_NOTIFICATIONS + 1 {
+            state = state.push_notification(&format!("Notification {}", i), "", Severity::WARNING, 60, t0());
+        }
+
+        assert_eq!(state.notifications.len(), MAX_NOTIFICATIONS);
+        assert_eq!(state.notifications[0].title, "Notification 1");
+    }
+
+    #[test]
+    fn keep_on_for_keeps_screen_on_without_presence() {
+        let state = absent_state().apply_action(&Action::KeepOnFor(60), &panels(), t0());
+
+        assert!(state.screen_on);
+        assert!(!state.apply_action(&Action::ScreenOff, &panels(), t0()).screen_on);
+    }
+
+    #[test]
+    fn schedule_forces_screen_off_and_on_in_auto() {
+        let quiet = State::init(t0()).apply_schedule(Some(ScheduleMode::OFF), t0());
+        assert!(!quiet.screen_on);
+        assert!(quiet.state_change_actions(&State::init(t0())) == vec![Action::ScreenOff]);
+
+        let forced = absent_state().apply_schedule(Some(ScheduleMode::ON), t0());
+        assert!(forced.screen_on);
+
+        let threshold_only = absent_state().apply_schedule(Some(ScheduleMode::THRESHOLD), t0());
+        assert!(!threshold_only.screen_on);
+    }
+
+    #[test]
+    fn manual_state_and_keep_on_beat_schedule() {
+        let quiet = State::init(t0()).apply_schedule(Some(ScheduleMode::OFF), t0());
+
+        assert!(quiet.apply_action(&Action::ScreenOn, &panels(), t0()).screen_on);
+        assert!(quiet.apply_action(&Action::KeepOnFor(60), &panels(), t0()).screen_on);
+        assert!(!absent_state().apply_action(&Action::ScreenOff, &panels(), t0()).apply_schedule(Some(ScheduleMode::ON), t0()).screen_on);
+    }
+
+    #[test]
+    fn idle_hosts_turn_screen_off_until_activity_resumes() {
+        let idle = State::init(t0()).apply_hosts_idle(true, t0());
+        assert!(!idle.screen_on);
+        assert!(idle.state_change_actions(&State::init(t0())) == vec![Action::ScreenOff]);
+
+        let resumed = idle.clone().apply_hosts_idle(false, t0());
+        assert!(resumed.screen_on);
+        assert!(resumed.state_change_actions(&idle) == vec![Action::ScreenOn]);
+    }
+
+    #[test]
+    fn forced_schedule_and_keep_on_beat_idle_hosts() {
+        assert!(State::init(t0()).apply_hosts_idle(true, t0()).apply_schedule(Some(ScheduleMode::ON), t0()).screen_on);
+        assert!(State::init(t0()).apply_hosts_idle(true, t0()).apply_action(&Action::KeepOnFor(60), &panels(), t0()).screen_on);
+    }
+
+    #[test]
+    fn keep_on_expires() {
+        let state = absent_state().apply_action(&Action::KeepOnFor(60), &panels(), t0());
+
+        assert!(state.clone().apply_hosts_idle(false, secs(59)).screen_on);
+        assert!(!state.apply_hosts_idle(false, secs(61)).screen_on);
+    }
+
+    #[test]
+    fn presence_goes_pending_then_absent_after_threshold() {
+        let pending = State::init(t0()).update_presence(false, 600, secs(10));
+        assert_eq!(pending.presence.present, Present::PENDING);
+        assert!(pending.screen_on);
+
+        let still_pending = pending.update_presence(false, 600, secs(610));
+        assert_eq!(still_pending.presence.present, Present::PENDING);
+
+        let absent = still_pending.clone().update_presence(false, 600, secs(611));
+        assert_eq!(absent.presence.present, Present::NO);
+        assert!(!absent.screen_on);
+        assert!(absent.state_change_actions(&still_pending) == vec![Action::ScreenOff]);
+    }
+
+    #[test]
+    fn presence_returns_from_pending_and_absent() {
+        let pending = State::init(t0()).update_presence(false, 600, secs(10));
+        assert_eq!(pending.clone().update_presence(true, 600, secs(20)).presence.present, Present::YES);
+
+        let absent = pending.update_presence(false, 600, secs(700));
+        assert_eq!(absent.update_presence(true, 600, secs(710)).presence.present, Present::YES);
+    }
+
+    #[test]
+    fn presence_survives_clock_before_last_switch() {
+        let pending = State::init(secs(100)).update_presence(false, 600, secs(100));
+
+        assert_eq!(pending.update_presence(false, 600, t0()).presence.present, Present::PENDING);
+    }
+
+    #[test]
+    fn panel_switches_after_switch_delay() {
+        let delay = Duration::from_secs(5);
+        let state = State::init(t0()).apply_panel_selection("linux".to_string(), delay, secs(1));
+        assert_eq!(state.active_panel, "pending");
+
+        let state = state.apply_panel_selection("linux".to_string(), delay, secs(5));
+        assert_eq!(state.active_panel, "pending");
+
+        let state = state.apply_panel_selection("linux".to_string(), delay, secs(6));
+        assert_eq!(state.active_panel, "linux");
+        assert_eq!(state.panel_candidate, None);
+    }
+
+    #[test]
+    fn late_report_does_not_flap_panel() {
+        let delay = Duration::from_secs(5);
+        let state = State::init(t0()).apply_panel_selection("linux".to_string(), Duration::ZERO, secs(1));
+        assert_eq!(state.active_panel, "linux");
+
+        let state = state.apply_panel_selection("pending".to_string(), delay, secs(10))
+            .apply_panel_selection("linux".to_string(), delay, secs(12))
+            .apply_panel_selection("pending".to_string(), delay, secs(14));
+
+        assert_eq!(state.active_panel, "linux");
+        assert_eq!(state.panel_candidate, Some(("pending".to_string(), secs(14))));
+    }
+
+    fn carousel_pages() -> Vec<CarouselPage> {
+        vec![
+            CarouselPage { panel: "linux".to_string(), dwell_secs: Some(5) },
+            CarouselPage { panel: "windows".to_string(), dwell_secs: None }
+        ]
+    }
+
+    fn carousel_config(resume_after_secs: Option<u64>) -> CarouselConfig {
+        CarouselConfig { dwell_secs: 15, resume_after_secs, indicator: true }
+    }
+
+    #[test]
+    fn carousel_cycles_pages_with_their_dwell_times() {
+        let (pages, config) = (carousel_pages(), carousel_config(None));
+        let panel_at = |state: &State| state.carousel.as_ref().map(|c| (c.page, c.panel.clone()));
+
+        let state = State::init(t0()).apply_carousel(&pages, &config, secs(0));
+        assert_eq!(panel_at(&state), Some((0, "linux".to_string())));
+
+        let state = state.apply_carousel(&pages, &config, secs(4));
+        assert_eq!(panel_at(&state), Some((0, "linux".to_string())));
+
+        let state = state.apply_carousel(&pages, &config, secs(5));
+        assert_eq!(panel_at(&state), Some((1, "windows".to_string())));
+
+        let state = state.apply_carousel(&pages, &config, secs(19))
+            .apply_carousel(&pages, &config, secs(20));
+        assert_eq!(panel_at(&state), Some((0, "linux".to_string())));
+        assert_eq!(state.carousel.as_ref().map(|c| c.pages), Some(2));
+
+        assert_eq!(state.apply_carousel(&[], &config, secs(21)).carousel, None);
+    }
+
+    #[test]
+    fn paused_carousel_stays_on_page_until_resumed() {
+        let (pages, config) = (carousel_pages(), carousel_config(None));
+        let state = State::init(t0()).apply_carousel(&pages, &config, secs(0))
+            .apply_action(&Action::ToggleCarousel, &panels(), secs(1))
+            .apply_carousel(&pages, &config, secs(60));
+        assert_eq!(state.carousel.as_ref().map(|c| c.page), Some(0));
+        assert_eq!(state.carousel.as_ref().and_then(|c| c.paused_since), Some(secs(1)));
+
+        let state = state.apply_action(&Action::ResumeCarousel, &panels(), secs(61))
+            .apply_carousel(&pages, &config, secs(65));
+        assert_eq!(state.carousel.as_ref().map(|c| c.page), Some(0));
+
+        let state = state.apply_carousel(&pages, &config, secs(66));
+        assert_eq!(state.carousel.as_ref().map(|c| c.page), Some(1));
+    }
+
+    #[test]
+    fn paused_carousel_resumes_after_timeout() {
+        let (pages, config) = (carousel_pages(), carousel_config(Some(30)));
+        let state = State::init(t0()).apply_carousel(&pages, &config, secs(0))
+            .apply_action(&Action::PauseCarousel, &panels(), secs(2))
+            .apply_carousel(&pages, &config, secs(31));
+        assert!(state.carousel.as_ref().is_some_and(|c| c.paused_since.is_some()));
+
+        let state = state.apply_carousel(&pages, &config, secs(32));
+        assert_eq!(state.carousel.as_ref().map(|c| (c.paused_since, c.page_since)), Some((None, secs(32))));
+    }
+}
@@ -0,0 +1,209 @@
+use raylib::camera::Camera2D;
+use raylib::math::{Rectangle, Vector2};
+
+// Panels and widgets are laid out in this space and scaled to the window.
+pub const VIRTUAL_WIDTH: i32 = 1024;
+pub const VIRTUAL_HEIGHT: i32 = 600;
+// Portrait layouts stack the two landscape columns on top of each other.
+pub const PORTRAIT_WIDTH: i32 = 520;
+pub const PORTRAIT_HEIGHT: i32 = 1200;
+
+pub const ROTATIONS: [u32; 4] = [0, 90, 180, 270];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Orientation {
+    LANDSCAPE,
+    PORTRAIT
+}
+
+impl Orientation {
+    pub fn of(width: i32, height: i32) -> Orientation {
+        if height > width { Orientation::PORTRAIT } else { Orientation::LANDSCAPE }
+    }
+
+    pub fn virtual_size(&self) -> (i32, i32) {
+        match self {
+            Orientation::LANDSCAPE => (VIRTUAL_WIDTH, VIRTUAL_HEIGHT),
+            Orientation::PORTRAIT => (PORTRAIT_WIDTH, PORTRAIT_HEIGHT)
+        }
+    }
+}
+
+// Maps the virtual space onto the window, scaled to fit and centered between black bars when the
+// aspect ratio differs. With a rotation the virtual space is fitted to the rotated window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub screen_width: i32,
+    pub screen_height: i32,
+    pub rotation: u32,
+    pub width: i32,
+    pub height: i32,
+    pub scale: f32,
+    pub offset_x: f32,
+    pub offset_y: f32
+}
+
+impl Viewport {
+    pub fn fit(screen_width: i32, screen_height: i32, rotation: u32) -> Viewport {
+        let (target_width, target_height) = rotated_size(screen_width, screen_height, rotation);
+        Viewport::fit_layout(screen_width, screen_height, rotation, Orientation::of(target_width, target_height))
+    }
+
+    fn fit_layout(screen_width: i32, screen_height: i32, rotation: u32, orientation: Orientation) -> Viewport {
+        let (target_width, target_height) = rotated_size(screen_width, screen_height, rotation);
+        let (width, height) = orientation.virtual_size();
+        let scale = (target_width as f32 / width as f32)
+            .min(target_height as f32 / height as f32)
+            .max(f32::MIN_POSITIVE);
+
+        Viewport {
+            screen_width,
+            screen_height,
+            rotation,
+            width,
+            height,
+            scale,
+            offset_x: ((target_width as f32 - width as f32 * scale) / 2.0).round(),
+            offset_y: ((target_height as f32 - height as f32 * scale) / 2.0).round()
+        }
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        Orientation::of(self.width, self.height)
+    }
+
+    // The same window fitted with the layout of another orientation, for panels without one.
+    pub fn with_orientation(&self, orientation: Orientation) -> Viewport {
+        Viewport::fit_layout(self.screen_width, self.screen_height, self.rotation, orientation)
+    }
+
+    pub fn is_scaled(&self) -> bool {
+        self.scale != 1.0
+    }
+
+    pub fn is_rotated(&self) -> bool {
+        self.rotation != 0
+    }
+
+    // Size of the render target the virtual space is drawn to, the window rotated.
+    pub fn target_size(&self) -> (i32, i32) {
+        rotated_size(self.screen_width, self.screen_height, self.rotation)
+    }
+
+    // Camera drawing virtual coordinates to the render target, moved by shift virtual pixels.
+    pub fn camera(&self, shift: (i32, i32)) -> Camera2D {
+        Camera2D {
+            offset: Vector2::new(self.offset_x + shift.0 as f32 * self.scale, self.offset_y + shift.1 as f32 * self.scale),
+            target: Vector2::zero(),
+            rotation: 0.0,
+            zoom: self.scale
+        }
+    }
+
+    // Areas of the render target outside the virtual space as (x, y, width, height).
+    pub fn bars(&self) -> Vec<(i32, i32, i32, i32)> {
+        let (target_width, target_height) = self.target_size();
+        let right = self.offset_x as i32 + (self.width as f32 * self.scale).round() as i32;
+        let bottom = self.offset_y as i32 + (self.height as f32 * self.scale).round() as i32;
+
+        vec![
+            (0, 0, self.offset_x as i32, target_height),
+            (right, 0, target_width - right, target_height),
+            (0, 0, target_width, self.offset_y as i32),
+            (0, bottom, target_width, target_height - bottom)
+        ].into_iter().filter(|(_, _, width, height)| *width > 0 && *height > 0).collect()
+    }
+
+    // Source and destination for drawing the rotated render target to the window, rotating
+    // clockwise around the window center. Render textures are stored upside down.
+    pub fn present(&self) -> (Rectangle, Rectangle, Vector2) {
+        let (target_width, target_height) = self.target_size();
+        let (width, height) = (target_width as f32, target_height as f32);
+
+        (
+            Rectangle::new(0.0, 0.0, width, -height),
+            Rectangle::new(self.screen_width as f32 / 2.0, self.screen_height as f32 / 2.0, width, height),
+            Vector2::new(width / 2.0, height / 2.0)
+        )
+    }
+}
+
+pub fn rotated_size(width: i32, height: i32, rotation: u32) -> (i32, i32) {
+    match rotation {
+        90 | 270 => (height, width),
+        _ => (width, height)
+    }
+}
+
+// Pixel size to load a font at so text drawn at size is rendered without scaling.
+pub fn scaled_font_size(size: i32, scale: f32) -> i32 {
+    ((size as f32 * scale).round() as i32).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn native_size_is_unscaled() {
+        let viewport = Viewport::fit(1024, 600, 0);
+
+        assert_eq!(viewport.scale, 1.0);
+        assert_eq!((viewport.offset_x, viewport.offset_y), (0.0, 0.0));
+        assert!(!viewport.is_scaled());
+        assert!(viewport.bars().is_empty());
+    }
+
+    #[test]
+    fn wider_screens_are_pillarboxed() {
+        let viewport = Viewport::fit(1920, 1080, 0);
+
+        assert_eq!(viewport.scale, 1.8);
+        assert_eq!((viewport.offset_x, viewport.offset_y), (38.0, 0.0));
+        assert_eq!(viewport.camera((0, 0)).offset, Vector2::new(38.0, 0.0));
+        assert_eq!(viewport.bars(), vec![(0, 0, 38, 1080), (1881, 0, 39, 1080)]);
+    }
+
+    #[test]
+    fn taller_screens_are_letterboxed() {
+        let viewport = Viewport::fit(800, 480, 0);
+
+        assert_eq!(viewport.scale, 800.0 / 1024.0);
+        assert_eq!((viewport.offset_x, viewport.offset_y), (0.0, 6.0));
+
+        let camera = viewport.camera((2, -2));
+        assert_eq!((camera.offset.x, camera.offset.y, camera.zoom), (1.5625, 4.4375, 0.78125));
+    }
+
+    #[test]
+    fn rotation_selects_portrait_layout() {
+        let viewport = Viewport::fit(1024, 600, 90);
+
+        assert_eq!(viewport.target_size(), (600, 1024));
+        assert_eq!(viewport.orientation(), Orientation::PORTRAIT);
+        assert_eq!((viewport.width, viewport.height), (PORTRAIT_WIDTH, PORTRAIT_HEIGHT));
+        assert_eq!(viewport.scale, 1024.0 / 1200.0);
+
+        let landscape = viewport.with_orientation(Orientation::LANDSCAPE);
+        assert_eq!((landscape.width, landscape.scale, landscape.offset_y), (VIRTUAL_WIDTH, 600.0 / 1024.0, 336.0));
+        assert_eq!(Viewport::fit(1024, 600, 180).orientation(), Orientation::LANDSCAPE);
+        assert_eq!(Viewport::fit(600, 1024, 0).orientation(), Orientation::PORTRAIT);
+    }
+
+    #[test]
+    fn presents_rotated_target_centered_on_window() {
+        let (source, dest, origin) = Viewport::fit(1024, 600, 270).present();
+
+        assert_eq!((source.width, source.height), (600.0, -1024.0));
+        assert_eq!((dest.x, dest.y, dest.width, dest.height), (512.0, 300.0, 600.0, 1024.0));
+        assert_eq!(origin, Vector2::new(300.0, 512.0));
+    }
+
+    #[test]
+    fn fonts_are_loaded_at_scaled_sizes() {
+        assert_eq!(scaled_font_size(20, 1.0), 20);
+        assert_eq!(scaled_font_size(25, 1.8), 45);
+        assert_eq!(scaled_font_size(13, 0.78125), 10);
+        assert_eq!(scaled_font_size(1, 0.1), 1);
+    }
+}
@@ -1,27 +1,28 @@
-use std::time::Instant;
-use serde::{Serialize, Deserialize, Deserializer};
+use serde::{Serialize, Deserialize};
 use serde_json::json;
 use std::collections::HashMap;
-use crate::config::Config;
 use std::sync::mpsc::{Sender, Receiver, RecvError};
+use std::io::ErrorKind;
 use std::sync::{mpsc, Arc};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::thread;
-use tungstenite::connect;
+use std::time::Duration;
+use tungstenite::{connect, Message};
+use tungstenite::stream::Stream;
 use reqwest::{Url, blocking};
 use crate::state::State;
 use crate::log::{Log, LogExt, LogLevel};
 use crate::context::Context;
+use crate::clock::Clock;
+use crate::reload::{self, ConfigStore};
 
-pub fn set_to_current_instant<'de, D>(_: D) -> Result<Instant, D::Error>
-    where
-        D: Deserializer<'de>,
-{
-    Ok(Instant::now())
-}
+const RETRY_MIN: Duration = Duration::from_secs(1);
+const RETRY_MAX: Duration = Duration::from_secs(60);
+// How long a read blocks before the connection checks whether it was superseded
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
 
-pub fn current_instant() -> Instant {
-    Instant::now()
-}
+// Bumped on every relay_setup, so only the newest connection keeps running
+static RELAY_GENERATION: AtomicU64 = AtomicU64::new(0);
 
 #[derive(Deserialize, Debug)]
 struct RegisterResponse {
@@ -32,31 +33,77 @@ struct RegisterResponse {
 pub struct SensorReport {
     pub(crate) reporter: String,
     pub(crate) topic: String,
-    pub(crate) sensors: HashMap<String, String>,
-    #[serde(default = "current_instant", deserialize_with="set_to_current_instant", skip_serializing)]
-    pub(crate) received: Instant
+    pub(crate) sensors: HashMap<String, String>
 }
 
 pub(crate) trait WebSocketExt {
-    fn receiver_loop(context: &Context, event_handler: fn(SensorReport, &mut State, &Config), error_handler: fn(RecvError));
+    fn receiver_loop(context: &Context, event_handler: impl Fn(SensorReport, &State, &Arc<ConfigStore>, &dyn Clock, &Sender<SensorReport>) -> State + Send + 'static, error_handler: fn(RecvError)) -> Sender<SensorReport>;
+    fn relay_setup(config: Arc<ConfigStore>, value_sender: Sender<SensorReport>);
 }
 
 pub struct WebSocket {}
 
 impl WebSocketExt for WebSocket {
-    fn receiver_loop(context: &Context, event_handler: fn(SensorReport, &mut State, &Config), error_handler: fn(RecvError)) {
-        let value_receiver = ws_client_setup(&context.config);
+    // Connects to the configured relay host, retrying with backoff while it can't be reached. A
+    // connection ends once a newer one is set up, i.e. when the host changes in the config.
+    fn relay_setup(config: Arc<ConfigStore>, value_sender: Sender<SensorReport>) {
+        let relay_host = config.load().relay_host.clone();
+        let generation = RELAY_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+        let is_current = move || RELAY_GENERATION.load(Ordering::SeqCst) == generation;
+
+        thread::spawn(move || {
+            let mut retry = RETRY_MIN;
+            let mut failing = false;
+
+            while is_current() {
+                let result = ws_register_client(&relay_host).and_then(|id| {
+                    Log::log(LogLevel::DEBUG, &*format!("Got WS ID: {}", id));
+                    ws_read_loop(format!("ws://{}/ws/{}", relay_host, id), &value_sender, &is_current, || {
+                        retry = RETRY_MIN;
+                        failing = false;
+                    })
+                });
+
+                if let Err(error) = result {
+                    if !is_current() {
+                        break;
+                    }
+                    Log::log(LogLevel::ERROR, &*format!("Relay {} failed, retrying in {}s: {}", relay_host, retry.as_secs(), error));
+                    if !failing && value_sender.send(relay_failure(&relay_host, &error)).is_err() {
+                        break;
+                    }
+                    failing = true;
+                    thread::sleep(retry);
+                    retry = (retry * 2).min(RETRY_MAX);
+                }
+            }
+
+            Log::log(LogLevel::INFO, &*format!("Relay host changed, disconnected from {}", relay_host));
+        });
+    }
+
+    fn receiver_loop(context: &Context, event_handler: impl Fn(SensorReport, &State, &Arc<ConfigStore>, &dyn Clock, &Sender<SensorReport>) -> State + Send + 'static, error_handler: fn(RecvError)) -> Sender<SensorReport> {
+        let (feedback_sender, value_receiver) = ws_client_setup(&context.config);
+        let input_sender = feedback_sender.clone();
         let thread_state = context.state.clone();
         let thread_config = context.config.clone();
+        let thread_clock = context.clock.clone();
+        let redraw_trigger = context.redraw.trigger();
 
         thread::spawn(move || {
-            loop {
-                let state = Arc::clone(&thread_state);
+            let mut current_state = thread_state.load_full();
 
+            loop {
                 match value_receiver.recv() {
                     Ok(event) => {
-                        if let Ok(mut locked_state) = state.lock() {
-                            event_handler(event, &mut *locked_state, &thread_config);
+                        let is_tick = event.topic == "tick";
+                        let new_state = Arc::new(event_handler(event, &current_state, &thread_config, &*thread_clock, &feedback_sender));
+                        let needs_redraw = !is_tick || new_state.screen_on != current_state.screen_on;
+                        thread_state.store(Arc::clone(&new_state));
+                        current_state = new_state;
+
+                        if needs_redraw {
+                            let _ = redraw_trigger.send(());
                         }
                     }
                     Err(error) => {
@@ -65,32 +112,45 @@ impl WebSocketExt for WebSocket {
                 }
             }
         });
+
+        return input_sender;
     }
 }
 
-fn ws_client_setup(config: &Config) -> Receiver<SensorReport> {
+fn ws_client_setup(config: &Arc<ConfigStore>) -> (Sender<SensorReport>, Receiver<SensorReport>) {
     let (tx, rx): (Sender<SensorReport>, Receiver<SensorReport>) = mpsc::channel();
-    let thread_tx = tx.clone();
-    let relay_host= config.relay_host.clone();
-    let thread_fn = move || {
-        let id = match ws_register_client(&relay_host) {
-            Err(error) => panic!("Failed to get WS URL: {}", error),
-            Ok(url) => url
-        };
 
-        Log::log(LogLevel::DEBUG, &*format!("Got WS ID: {}", id));
+    WebSocket::relay_setup(config.clone(), tx.clone());
+    tick_setup(tx.clone());
+    reload::watch_setup(config.clone(), tx.clone());
 
-        ws_read_loop(format!("ws://{}/ws/{}", relay_host, id), thread_tx);
-    };
+    return (tx, rx);
+}
+
+fn tick_setup(tick_tx: Sender<SensorReport>) {
+    thread::spawn(move || {
+        loop {
+            thread::sleep(Duration::from_secs(1));
 
-    thread::spawn(thread_fn);
+            let tick = SensorReport {
+                reporter: "sensorpanel".to_string(),
+                topic: "tick".to_string(),
+                sensors: HashMap::new()
+            };
 
-    return rx;
+            if tick_tx.send(tick).is_err() {
+                break;
+            }
+        }
+    });
 }
 
-fn ws_read_loop(url: String, value_sender: Sender<SensorReport>) {
-    let (mut socket, response) =
-        connect(Url::parse(&url).unwrap()).expect("Can't connect");
+// Reads reports until the connection fails or is superseded. on_connect is called once the
+// handshake succeeded.
+fn ws_read_loop(url: String, value_sender: &Sender<SensorReport>, is_current: &impl Fn() -> bool, on_connect: impl FnOnce()) -> Result<(), String> {
+    let (mut socket, response) = connect(Url::parse(&url).map_err(|error| error.to_string())?)
+        .map_err(|error| format!("Can't connect to {}: {}", url, error))?;
+    on_connect();
 
     Log::log(LogLevel::DEBUG, "Connected to the server");
     Log::log(LogLevel::DEBUG, &*format!("Response HTTP code: {}", response.status()));
@@ -99,20 +159,46 @@ fn ws_read_loop(url: String, value_sender: Sender<SensorReport>) {
         Log::log(LogLevel::DEBUG,&*format!("* {}", header));
     }
 
-    loop {
-        let msg = socket.read_message().expect("Error reading message");
-        let report: SensorReport = serde_json::from_str(msg.to_text().unwrap()).unwrap();
-        let result = value_sender.send(report);
-        match result {
-            Err(error) => { Log::log(LogLevel::ERROR, &*format!("Failed to send request: {}", error))}
-            _ => {}
+    let stream = match socket.get_ref() {
+        Stream::Plain(stream) => stream,
+        Stream::Tls(stream) => stream.get_ref()
+    };
+    stream.set_read_timeout(Some(READ_TIMEOUT)).map_err(|error| error.to_string())?;
+
+    while is_current() {
+        let msg = match socket.read_message() {
+            Ok(msg) => msg,
+            Err(tungstenite::Error::Io(error)) if error.kind() == ErrorKind::WouldBlock || error.kind() == ErrorKind::TimedOut => continue,
+            Err(error) => return Err(format!("Error reading message: {}", error))
+        };
+
+        let text = match msg {
+            Message::Text(text) => text,
+            Message::Close(_) => return Err("Connection closed by the relay".to_string()),
+            _ => continue
+        };
+
+        let report: SensorReport = match serde_json::from_str(&text) {
+            Ok(report) => report,
+            Err(error) => {
+                Log::log(LogLevel::ERROR, &*format!("Invalid report from relay: {}", error));
+                continue;
+            }
+        };
+
+        if let Err(error) = value_sender.send(report) {
+            Log::log(LogLevel::ERROR, &*format!("Failed to send request: {}", error));
         }
     }
+
+    let _ = socket.close(None);
+    let _ = socket.write_pending();
+    Ok(())
 }
 
-fn ws_register_client(relay_host: &String) -> Result<String, reqwest::Error> {
+fn ws_register_client(relay_host: &String) -> Result<String, String> {
     let register_body = json!({
-        "topics": ["sensors", "actions"],
+        "topics": ["sensors", "actions", "notifications"],
     });
 
     let request_url = format!("http://{}/register", relay_host);
@@ -120,18 +206,26 @@ fn ws_register_client(relay_host: &String) -> Result<String, reqwest::Error> {
     let response = blocking::Client::new()
         .post(request_url)
         .json(&register_body)
-        .send();
+        .send()
+        .map_err(|error| format!("Request failed: {}", error))?;
+    Log::log(LogLevel::DEBUG, "Request OK");
 
-    let response = match response {
-        Err(error) => panic!("Request failed: {}", error),
-        Ok(response) => { Log::log(LogLevel::DEBUG, "Request OK"); response }
-}
-        ;
-
-    let register_response: RegisterResponse = match response.json() {
-        Err(error) => panic!("Parse json failed: {:?}", error),
-        Ok(json) => json
-    };
+    let register_response: RegisterResponse = response.json()
+        .map_err(|error| format!("Parse json failed: {:?}", error))?;
 
     Ok(register_response.id)
 }
+
+// Shown once per failure streak, not on every retry.
+fn relay_failure(relay_host: &str, error: &str) -> SensorReport {
+    let mut sensors = HashMap::new();
+    sensors.insert("title".to_string(), format!("Relay {} unreachable", relay_host));
+    sensors.insert("body".to_string(), error.to_string());
+    sensors.insert("severity".to_string(), "error".to_string());
+
+    SensorReport {
+        reporter: "sensorpanel".to_string(),
+        topic: "notifications".to_string(),
+        sensors
+    }
+}
@@ -0,0 +1,134 @@
+use raylib::prelude::*;
+use raylib::core::window::get_monitor_count;
+use crate::config::{WindowConfig, WindowMode};
+use crate::log::{Log, LogExt, LogLevel};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MonitorArea {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub width: i32,
+    pub height: i32,
+    pub position: Option<(i32, i32)>
+}
+
+// Size and position of the window. x and y are relative to the selected monitor, a window on a
+// monitor without a position is centered on it and borderless windows cover the whole monitor.
+pub fn placement(config: &WindowConfig, monitor: Option<MonitorArea>) -> Placement {
+    match (&config.mode, monitor) {
+        (WindowMode::BORDERLESS, Some(area)) => Placement {
+            width: area.width,
+            height: area.height,
+            position: Some((area.x, area.y))
+        },
+        (WindowMode::FULLSCREEN, _) => Placement { width: config.width, height: config.height, position: None },
+        (_, area) => {
+            let origin = area.map(|area| (area.x, area.y)).unwrap_or((0, 0));
+            let position = match (config.x, config.y, area) {
+                (None, None, Some(area)) => Some((area.x + (area.width - config.width) / 2, area.y + (area.height - config.height) / 2)),
+                (None, None, None) => None,
+                (x, y, _) => Some((origin.0 + x.unwrap_or(0), origin.1 + y.unwrap_or(0)))
+            };
+
+            Placement { width: config.width, height: config.height, position }
+        }
+    }
+}
+
+fn monitor_area(monitor: i32) -> MonitorArea {
+    let position = unsafe { raylib::ffi::GetMonitorPosition(monitor) };
+
+    MonitorArea {
+        x: position.x as i32,
+        y: position.y as i32,
+        width: get_monitor_width(monitor),
+        height: get_monitor_height(monitor)
+    }
+}
+
+pub fn open_window(config: &WindowConfig) -> (RaylibHandle, RaylibThread) {
+    let mut builder = raylib::init();
+    builder.size(config.width, config.height).title(&config.title);
+
+    match config.mode {
+        WindowMode::FULLSCREEN if config.monitor.is_none() => { builder.fullscreen(); },
+        WindowMode::BORDERLESS => { builder.undecorated(); },
+        _ => {}
+    }
+
+    let (mut handle, thread) = builder.build();
+
+    let monitor = config.monitor.filter(|monitor| {
+        let count = get_monitor_count();
+        if *monitor >= count {
+            Log::log(LogLevel::INFO, &*format!("Monitor {} not found, {} connected, using the default monitor", monitor, count));
+        }
+        *monitor < count
+    });
+
+    let placement = placement(config, monitor.map(monitor_area));
+    Log::log(LogLevel::DEBUG, &*format!("Window {:?} on monitor {:?}: {:?}", config.mode, monitor, placement));
+
+    match (&config.mode, monitor) {
+        // Switches to fullscreen on the monitor using its current video mode
+        (WindowMode::FULLSCREEN, Some(monitor)) => handle.set_window_monitor(monitor),
+        (WindowMode::FULLSCREEN, None) => {},
+        _ => {
+            handle.set_window_size(placement.width, placement.height);
+            if let Some((x, y)) = placement.position {
+                handle.set_window_position(x, y);
+            }
+        }
+    }
+
+    if config.hide_cursor {
+        handle.hide_cursor();
+    }
+
+    return (handle, thread);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn window(source: &str) -> WindowConfig {
+        let config: Config = toml::from_str(&format!("[window]\n{}", source)).unwrap();
+        config.window
+    }
+
+    const SECONDARY: MonitorArea = MonitorArea { x: 1920, y: 0, width: 2560, height: 1440 };
+
+    #[test]
+    fn windowed_defaults_leave_position_to_the_window_manager() {
+        assert_eq!(placement(&window(""), None), Placement { width: 1024, height: 600, position: None });
+    }
+
+    #[test]
+    fn windowed_is_centered_or_offset_on_selected_monitor() {
+        assert_eq!(placement(&window("monitor = 1"), Some(SECONDARY)).position, Some((1920 + 768, 420)));
+        assert_eq!(placement(&window("monitor = 1\nx = 10\ny = 20"), Some(SECONDARY)).position, Some((1930, 20)));
+        assert_eq!(placement(&window("x = 10"), None).position, Some((10, 0)));
+    }
+
+    #[test]
+    fn borderless_covers_the_monitor() {
+        assert_eq!(placement(&window("mode = 'borderless'"), Some(SECONDARY)),
+                   Placement { width: 2560, height: 1440, position: Some((1920, 0)) });
+        assert_eq!(placement(&window("mode = 'borderless'\nwidth = 800\nheight = 480"), None),
+                   Placement { width: 800, height: 480, position: None });
+    }
+
+    #[test]
+    fn fullscreen_uses_configured_size() {
+        assert_eq!(placement(&window("mode = 'fullscreen'\nwidth = 800\nheight = 480"), Some(SECONDARY)),
+                   Placement { width: 800, height: 480, position: None });
+    }
+}
@@ -2,8 +2,9 @@ use raylib::prelude::*;
 use std::collections::HashMap;
 use crate::fonts::get_font;
 use crate::data::SensorData;
+use crate::layout::Keys;
 
-pub fn draw_cpu_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &Vec<&SensorData>) {
+pub fn draw_cpu_panel<D: RaylibDraw>(mut d: &mut D, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &Vec<&SensorData>, keys: Keys, title: &str) {
 
     let xf = x as f32;
     let yf = y as f32;
@@ -18,16 +19,16 @@ pub fn draw_cpu_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &Hash
         .max()
         .unwrap_or(0);
 
-    let cpu_utilization: f32 = latest_data.values.get("cpu_utilization").unwrap_or(&"0".to_string()).parse().unwrap();
-    let cpu_die_temp: f32 = latest_data.values.get("cpu_die_temp").unwrap_or(&"0".to_string()).parse().unwrap();
-    let cpu_package_temp: f32 = latest_data.values.get("cpu_package_temp").unwrap_or(&"0".to_string()).parse().unwrap();
-    let cpu_power: f32 = latest_data.values.get("cpu_power").unwrap_or(&"0".to_string()).parse().unwrap();
+    let cpu_utilization: f32 = latest_data.values.get(keys.get("cpu_utilization")).unwrap_or(&"0".to_string()).parse().unwrap();
+    let cpu_die_temp: f32 = latest_data.values.get(keys.get("cpu_die_temp")).unwrap_or(&"0".to_string()).parse().unwrap();
+    let cpu_package_temp: f32 = latest_data.values.get(keys.get("cpu_package_temp")).unwrap_or(&"0".to_string()).parse().unwrap();
+    let cpu_power: f32 = latest_data.values.get(keys.get("cpu_power")).unwrap_or(&"0".to_string()).parse().unwrap();
 
-    d.draw_text_ex(get_font(fonts, "calibri_50_bold"), "CPU", Vector2::new(xf + 10.0, yf + 10.0), 50.0, 0.0, Color::WHITE);
+    d.draw_text_ex(get_font(fonts, "calibri_50_bold"), title, Vector2::new(xf + 10.0, yf + 10.0), 50.0, 0.0, Color::WHITE);
     d.draw_text_ex(get_font(fonts, "calibri_20"), &*format!("{:.2} W", cpu_power), Vector2::new(xf + 110.0, yf + 21.0), 20.0, 0.0, Color::WHITE);
 
-    draw_temperature_gauge(&mut d, x + 200, y + 5, cpu_die_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
-    draw_temperature_gauge(&mut d, x + 275, y + 5, cpu_package_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
+    draw_temperature_gauge(d, x + 200, y + 5, cpu_die_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
+    draw_temperature_gauge(d, x + 275, y + 5, cpu_package_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
     d.draw_text_ex(get_font(fonts, "calibri_30"), &*format!("{} MHz", max_core_frequency), Vector2::new(xf + 340.0, yf + 18.0), 30.0, 0.0, Color::WHITE);
 
     d.draw_text_ex(get_font(fonts, "calibri_25_bold"), "Usage", Vector2::new(xf + 10.0, yf + 65.0), 25.0, 0.0, Color::WHITE);
@@ -38,7 +39,7 @@ pub fn draw_cpu_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &Hash
     draw_graph_grid(&mut d, x + 10, y + 100);
 
     let usage_graph_values = &data.iter()
-        .map(|d| d.values.get("cpu_utilization"))
+        .map(|d| d.values.get(keys.get("cpu_utilization")))
         .filter(|util| util.is_some())
         .map(|v| v.unwrap().parse::<f32>().unwrap())
         .collect();
@@ -46,27 +47,27 @@ pub fn draw_cpu_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &Hash
     draw_graph(&mut d, x + 10, y + 100, usage_graph_values, Color::GREEN);
 }
 
-pub fn draw_gpu_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &Vec<&SensorData>) {
+pub fn draw_gpu_panel<D: RaylibDraw>(mut d: &mut D, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &Vec<&SensorData>, keys: Keys, title: &str) {
 
     let xf = x as f32;
     let yf = y as f32;
 
     let latest_data = data.last().unwrap();
 
-    let gpu_utilization: f32 = latest_data.values.get("gpu_utilization").unwrap_or(&"0".to_string()).parse().unwrap();
-    let gpu_die_temp: f32 = latest_data.values.get("gpu_die_temp").unwrap_or(&"0".to_string()).parse().unwrap();
-    let gpu_package_temp: f32 = latest_data.values.get("gpu_package_temp").unwrap_or(&"0".to_string()).parse().unwrap();
-    let gpu_power: f32 = latest_data.values.get("gpu_power").unwrap_or(&"0".to_string()).parse().unwrap();
-    let gpu_voltage: f32 = latest_data.values.get("gpu_voltage").unwrap_or(&"0".to_string()).parse().unwrap();
-    let gpu_frequency: f32 = latest_data.values.get("gpu_frequency").unwrap_or(&"0".to_string()).parse().unwrap();
-    let gpu_fps: f32 = latest_data.values.get("gpu_fps").unwrap_or(&"0".to_string()).parse().unwrap();
+    let gpu_utilization: f32 = latest_data.values.get(keys.get("gpu_utilization")).unwrap_or(&"0".to_string()).parse().unwrap();
+    let gpu_die_temp: f32 = latest_data.values.get(keys.get("gpu_die_temp")).unwrap_or(&"0".to_string()).parse().unwrap();
+    let gpu_package_temp: f32 = latest_data.values.get(keys.get("gpu_package_temp")).unwrap_or(&"0".to_string()).parse().unwrap();
+    let gpu_power: f32 = latest_data.values.get(keys.get("gpu_power")).unwrap_or(&"0".to_string()).parse().unwrap();
+    let gpu_voltage: f32 = latest_data.values.get(keys.get("gpu_voltage")).unwrap_or(&"0".to_string()).parse().unwrap();
+    let gpu_frequency: f32 = latest_data.values.get(keys.get("gpu_frequency")).unwrap_or(&"0".to_string()).parse().unwrap();
+    let gpu_fps: f32 = latest_data.values.get(keys.get("gpu_fps")).unwrap_or(&"0".to_string()).parse().unwrap();
 
-    d.draw_text_ex(get_font(fonts, "calibri_50_bold"), "GPU", Vector2::new(xf + 10.0, yf + 10.0), 50.0, 0.0, Color::WHITE);
+    d.draw_text_ex(get_font(fonts, "calibri_50_bold"), title, Vector2::new(xf + 10.0, yf + 10.0), 50.0, 0.0, Color::WHITE);
     d.draw_text_ex(get_font(fonts, "calibri_20"), &*format!("{:.2} W", gpu_power), Vector2::new(xf + 110.0,  yf + 12.0), 20.0, 0.0, Color::WHITE);
     d.draw_text_ex(get_font(fonts, "calibri_20"), &*format!("{:.2} V", gpu_voltage), Vector2::new(xf + 110.0, yf + 30.0), 20.0, 0.0, Color::WHITE);
 
-    draw_temperature_gauge(&mut d, x + 200, y + 5, gpu_die_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
-    draw_temperature_gauge(&mut d, x + 275, y + 5, gpu_package_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
+    draw_temperature_gauge(d, x + 200, y + 5, gpu_die_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
+    draw_temperature_gauge(d, x + 275, y + 5, gpu_package_temp as i32, get_font(fonts, "calibri_20"), get_font(fonts, "calibri_13"));
 
     d.draw_text_ex(get_font(fonts, "calibri_30"), &*format!("{} MHz", gpu_frequency), Vector2::new(xf + 340.0, yf + 3.0), 30.0, 0.0, Color::WHITE);
     d.draw_text_ex(get_font(fonts, "calibri_30"), &*format!("{} FPS", gpu_fps), Vector2::new(xf + 340.0, yf + 32.0), 30.0, 0.0, Color::WHITE);
@@ -78,7 +79,7 @@ pub fn draw_gpu_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &Hash
     draw_graph_grid(&mut d, x + 10, y + 100);
 
     let usage_graph_values = &data.iter()
-        .map(|d| d.values.get("gpu_utilization"))
+        .map(|d| d.values.get(keys.get("gpu_utilization")))
         .filter(|util| util.is_some())
         .map(|v| v.unwrap().parse::<f32>().unwrap())
         .collect();
@@ -86,7 +87,7 @@ pub fn draw_gpu_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &Hash
     draw_graph(&mut d, x + 10, y + 100, usage_graph_values, Color::RED);
 }
 
-pub fn draw_net_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &Vec<&SensorData>) {
+pub fn draw_net_panel<D: RaylibDraw>(mut d: &mut D, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &Vec<&SensorData>) {
 
     let xf = x as f32;
     let yf = y as f32;
@@ -142,14 +143,14 @@ pub fn draw_net_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &Hash
 
 fn bytes_to_mbit(bytes: i64) -> f32 { (bytes * 8) as f32 / 1000000.0 }
 
-pub fn draw_mem_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &Vec<&SensorData>) {
+pub fn draw_mem_panel<D: RaylibDraw>(mut d: &mut D, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &Vec<&SensorData>, keys: Keys) {
 
     let xf = x as f32;
     let yf = y as f32;
     let latest_data = data.last().unwrap();
 
-    let mem_used : f32 = latest_data.values.get("mem_used").unwrap_or(&"0".to_string()).parse().unwrap();
-    let mem_available : f32 = latest_data.values.get("mem_available").unwrap_or(&"0".to_string()).parse().unwrap();
+    let mem_used : f32 = latest_data.values.get(keys.get("mem_used")).unwrap_or(&"0".to_string()).parse().unwrap();
+    let mem_available : f32 = latest_data.values.get(keys.get("mem_available")).unwrap_or(&"0".to_string()).parse().unwrap();
     let mem_used_percent = mem_used / (mem_used + mem_available);
 
     d.draw_text_ex(get_font(fonts, "calibri_50_bold"), "Memory", Vector2::new(xf + 10.0, yf + 10.0), 50.0, 0.0, Color::WHITE);
@@ -162,7 +163,7 @@ pub fn draw_mem_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &Hash
     draw_meter_bar(&mut d, x + 80, y + 65, 400, 23, (mem_used_percent * 100.0) as i32, 100, (gradient_color_1, gradient_color_2), fonts);
 }
 
-pub fn draw_core_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &Vec<&SensorData>) {
+pub fn draw_core_panel<D: RaylibDraw>(mut d: &mut D, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &Vec<&SensorData>) {
 
     let gradient_color_1 = Color::new(0, 200, 0, 255);
     let gradient_color_2 = Color::new(0, 40, 0, 255);
@@ -188,7 +189,7 @@ pub fn draw_core_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &Has
     }
 }
 
-pub fn draw_hdd_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &Vec<&SensorData>)
+pub fn draw_hdd_panel<D: RaylibDraw>(mut d: &mut D, x: i32, y: i32, fonts: &HashMap<String, Font>, data: &Vec<&SensorData>)
 {
     let latest_data = data.last().unwrap();
 
@@ -214,7 +215,7 @@ pub fn draw_hdd_panel(mut d: &mut RaylibDrawHandle, x: i32, y: i32, fonts: &Hash
 
 fn bytes_to_gigabytes(bytes: i64) -> i64 { bytes / 1024 / 1024 / 1024 }
 
-pub fn draw_graph_grid(d: &mut &mut RaylibDrawHandle, x: i32, y: i32) {
+pub fn draw_graph_grid<D: RaylibDraw>(d: &mut &mut D, x: i32, y: i32) {
     let grid_color = Color::new(49, 50, 50, 255);
 
     d.draw_rectangle(x, y, 460, 80, Color::DARKGRAY);
@@ -229,7 +230,7 @@ pub fn draw_graph_grid(d: &mut &mut RaylibDrawHandle, x: i32, y: i32) {
     }
 }
 
-fn draw_graph(d: &mut &mut RaylibDrawHandle, x: i32, y: i32, historical: &Vec<f32>, color: Color) {
+fn draw_graph<D: RaylibDraw>(d: &mut &mut D, x: i32, y: i32, historical: &Vec<f32>, color: Color) {
 
     if historical.len() == 0 {
         return;
@@ -246,12 +247,12 @@ fn draw_graph(d: &mut &mut RaylibDrawHandle, x: i32, y: i32, historical: &Vec<f3
     }
 }
 
-pub fn draw_meter_bar(d: &mut &mut RaylibDrawHandle, x: i32, y: i32, width: i32, height: i32, value: i32, max_value: i32, color: (Color, Color), fonts: &HashMap<String, Font>) {
+pub fn draw_meter_bar<D: RaylibDraw>(d: &mut &mut D, x: i32, y: i32, width: i32, height: i32, value: i32, max_value: i32, color: (Color, Color), fonts: &HashMap<String, Font>) {
     let label = format!("{} %", value.to_string());
     draw_meter_bar_with_label(d, x, y, width, height, value, max_value, color, fonts, label, width as f32 / 2.0 - 15.0, Color::WHITE);
 }
 
-pub fn draw_meter_bar_with_label(d: &mut &mut RaylibDrawHandle, x: i32, y: i32, width: i32, height: i32, value: i32, max_value: i32, color: (Color, Color), fonts: &HashMap<String, Font>, label: String, label_pos: f32, label_color: Color) {
+pub fn draw_meter_bar_with_label<D: RaylibDraw>(d: &mut &mut D, x: i32, y: i32, width: i32, height: i32, value: i32, max_value: i32, color: (Color, Color), fonts: &HashMap<String, Font>, label: String, label_pos: f32, label_color: Color) {
     d.draw_rectangle(x, y, width, height, Color::DARKGRAY);
     d.draw_rectangle(x + 1, y + 1, width - 2, height - 2, Color::BLACK);
 
@@ -262,7 +263,7 @@ pub fn draw_meter_bar_with_label(d: &mut &mut RaylibDrawHandle, x: i32, y: i32,
     d.draw_text_ex(get_font(fonts, "calibri_20"), &label, Vector2::new(label_pos + x as f32, y as f32 + 3.0), 20.0, 0.0, label_color);
 }
 
-pub fn draw_temperature_gauge(d: &mut RaylibDrawHandle, x: i32, y: i32, value: i32, font: &Font, font2: &Font) {
+pub fn draw_temperature_gauge<D: RaylibDraw>(d: &mut D, x: i32, y: i32, value: i32, font: &Font, font2: &Font) {
     d.draw_circle(x + 25, y + 25, 25.0, Color::LIGHTGRAY);
     d.draw_circle(x + 25, y + 25, 23.0, Color::BLACK);
 